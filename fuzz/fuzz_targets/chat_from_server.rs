@@ -0,0 +1,10 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use std::sync::{Arc, Mutex};
+use proiect_rust::chat::{chat_from_server, Chat};
+use proiect_rust::players::Players;
+
+fuzz_target!(|data: &[u8]| {
+    let _ = chat_from_server(data.to_vec(), Arc::new(Mutex::new(Chat::default())), Arc::new(Mutex::new(Players::new())));
+});
@@ -0,0 +1,20 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use proiect_rust::codec::read_var_int;
+use proiect_rust::frame::{read_frame, PacketDecoder};
+use std::io::Cursor;
+
+// Feeds the input through the same path as `packet_monitoring`: split it into
+// frames, then decompress every frame that claims to be compressed.
+fuzz_target!(|data: &[u8]| {
+    let mut stream = Cursor::new(data);
+    let mut decoder = PacketDecoder::new(256);
+    while let Ok(frame) = read_frame(&mut stream) {
+        if let Ok(vec) = read_var_int(&frame) {
+            if vec[0] != 0 {
+                let _ = decoder.decode(vec[0], &frame[vec[1] as usize..]);
+            }
+        }
+    }
+});
@@ -0,0 +1,11 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use proiect_rust::codec::read_var_int;
+use proiect_rust::frame::PacketDecoder;
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(vec) = read_var_int(data) {
+        let _ = PacketDecoder::new(0).decode(vec[0], &data[vec[1] as usize..]);
+    }
+});
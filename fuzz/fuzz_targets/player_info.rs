@@ -0,0 +1,9 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use proiect_rust::players::{player_info, Players};
+use std::sync::{Arc, Mutex};
+
+fuzz_target!(|data: &[u8]| {
+    let _ = player_info(data.to_vec(), Arc::new(Mutex::new(Players::new())));
+});
@@ -0,0 +1,10 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use proiect_rust::players::Players;
+use proiect_rust::teams::teams;
+use std::sync::{Arc, Mutex};
+
+fuzz_target!(|data: &[u8]| {
+    let _ = teams(data.to_vec(), Arc::new(Mutex::new(Players::new())));
+});
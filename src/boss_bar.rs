@@ -0,0 +1,227 @@
+use std::io;
+use std::io::Cursor;
+use std::io::{Error, ErrorKind};
+use std::sync::{Arc, Mutex};
+use crate::codec::{read_byte, read_float, read_string, read_uuid, read_var_int_from_stream};
+use crate::component::{Color, Component};
+use crate::text_formatting::to_ansi;
+use crate::uuid::Uuid;
+
+/// How many cells wide a boss bar is printed.
+pub const BAR_WIDTH: usize = 20;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct BossBar {
+    pub uuid: Uuid,
+    pub title: Component,
+    /// From 0 to 1.
+    pub health: f32,
+    /// The bar color as a chat color name.
+    pub color: &'static str,
+    /// How many notches the bar is divided in, 0 for none.
+    pub notches: u8,
+    pub flags: u8,
+}
+
+/// The chat colors closest to the boss bar colors, in protocol order.
+const BAR_COLORS: [&str; 7] = ["light_purple", "blue", "red", "green", "yellow", "dark_purple", "white"];
+const NOTCHES: [u8; 5] = [0, 6, 10, 12, 20];
+
+impl BossBar {
+    /// The bar as one line: the filled part in its color, then the title
+    /// and the percentage.
+    pub fn to_component(&self) -> Component {
+        let filled = ((self.health.clamp(0.0, 1.0) * BAR_WIDTH as f32).round() as usize).min(BAR_WIDTH);
+        let mut bar = Component::text(&"█".repeat(filled));
+        bar.style.color = Color::parse(self.color);
+        let mut empty = Component::text(&"░".repeat(BAR_WIDTH - filled));
+        empty.style.color = Color::parse("dark_gray");
+        Component {
+            extra: vec![
+                bar,
+                empty,
+                Component::text(" "),
+                self.title.clone(),
+                Component::text(&format!(" {}%", (self.health.clamp(0.0, 1.0) * 100.0).round())),
+            ],
+            ..Component::default()
+        }
+    }
+}
+
+#[derive(Clone, Default)]
+pub struct BossBars {
+    bars: Vec<BossBar>,
+}
+
+impl BossBars {
+    pub fn new() -> BossBars {
+        BossBars::default()
+    }
+
+    pub fn bars(&self) -> &[BossBar] {
+        &self.bars
+    }
+
+    pub fn find(&self, uuid: Uuid) -> Option<&BossBar> {
+        self.bars.iter().find(|bar| bar.uuid == uuid)
+    }
+
+    fn find_mut(&mut self, uuid: Uuid) -> Option<&mut BossBar> {
+        self.bars.iter_mut().find(|bar| bar.uuid == uuid)
+    }
+
+    /// Prints the bars top to bottom, in the order they were added.
+    pub fn print_bars(&self) {
+        if self.bars.is_empty() {
+            println!("There are no boss bars");
+        }
+        for bar in &self.bars {
+            println!("{}", to_ansi(&bar.to_component()));
+        }
+    }
+}
+
+fn read_title(reader: &mut Cursor<Vec<u8>>) -> io::Result<Component> {
+    let json = read_string(reader)?;
+    serde_json::from_str(&json).map_err(|_| Error::new(ErrorKind::InvalidData, "Boss bar title is not a valid chat component"))
+}
+
+fn read_style(reader: &mut Cursor<Vec<u8>>) -> io::Result<(&'static str, u8)> {
+    let color = read_var_int_from_stream(reader)?;
+    let color = usize::try_from(color).ok().and_then(|color| BAR_COLORS.get(color));
+    let division = read_var_int_from_stream(reader)?;
+    let notches = usize::try_from(division).ok().and_then(|division| NOTCHES.get(division));
+    match (color, notches) {
+        (Some(color), Some(notches)) => Ok((color, *notches)),
+        _ => Err(Error::new(ErrorKind::InvalidData, "Unknown boss bar style")),
+    }
+}
+
+/// Reads a Boss Bar packet. New bars and new titles are printed, as that is
+/// when events announce something, health changes are only kept.
+pub fn boss_bar(buffer: Vec<u8>, boss_bars: Arc<Mutex<BossBars>>) -> io::Result<()> {
+    let mut reader = Cursor::new(buffer);
+    let uuid = read_uuid(&mut reader)?;
+    let action = read_var_int_from_stream(&mut reader)?;
+    let mut boss_bars = boss_bars.lock().unwrap();
+    match action {
+        0 => {
+            let title = read_title(&mut reader)?;
+            let health = read_float(&mut reader)?;
+            let (color, notches) = read_style(&mut reader)?;
+            let flags = read_byte(&mut reader)?;
+            let bar = BossBar { uuid, title, health, color, notches, flags };
+            println!("[Boss bar] {}", to_ansi(&bar.to_component()));
+            boss_bars.bars.retain(|bar| bar.uuid != uuid);
+            boss_bars.bars.push(bar);
+        }
+
+        1 => {
+            boss_bars.bars.retain(|bar| bar.uuid != uuid);
+        }
+
+        2 => {
+            let health = read_float(&mut reader)?;
+            if let Some(bar) = boss_bars.find_mut(uuid) {
+                bar.health = health;
+            }
+        }
+
+        3 => {
+            let title = read_title(&mut reader)?;
+            if let Some(bar) = boss_bars.find_mut(uuid) {
+                bar.title = title;
+                println!("[Boss bar] {}", to_ansi(&bar.to_component()));
+            }
+        }
+
+        4 => {
+            let (color, notches) = read_style(&mut reader)?;
+            if let Some(bar) = boss_bars.find_mut(uuid) {
+                bar.color = color;
+                bar.notches = notches;
+            }
+        }
+
+        5 => {
+            let flags = read_byte(&mut reader)?;
+            if let Some(bar) = boss_bars.find_mut(uuid) {
+                bar.flags = flags;
+            }
+        }
+
+        _ => {
+            return Err(Error::new(ErrorKind::InvalidData, "Unknown boss bar action"));
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::codec::{write_float, write_string, write_uuid, write_var_int};
+    use proptest::prelude::*;
+
+    fn bar_packet(uuid: Uuid, action: i32, write: impl FnOnce(&mut Vec<u8>)) -> Vec<u8> {
+        let mut buffer: Vec<u8> = Vec::new();
+        write_uuid(&mut buffer, uuid).unwrap();
+        write_var_int(&mut buffer, action).unwrap();
+        write(&mut buffer);
+        buffer
+    }
+
+    #[test]
+    fn bars_are_added_updated_and_removed() {
+        let boss_bars = Arc::new(Mutex::new(BossBars::new()));
+        let (dragon, event) = (Uuid(1), Uuid(2));
+        for uuid in [dragon, event] {
+            let packet = bar_packet(uuid, 0, |buffer| {
+                write_string(buffer, r#"{"text":"Ender Dragon"}"#).unwrap();
+                write_float(buffer, 1.0).unwrap();
+                write_var_int(buffer, 0).unwrap();
+                write_var_int(buffer, 2).unwrap();
+                buffer.push(0x01);
+            });
+            boss_bar(packet, Arc::clone(&boss_bars)).unwrap();
+        }
+        boss_bar(bar_packet(dragon, 2, |buffer| write_float(buffer, 0.25).unwrap()), Arc::clone(&boss_bars)).unwrap();
+        boss_bar(bar_packet(dragon, 3, |buffer| write_string(buffer, r#""Dragon""#).unwrap()), Arc::clone(&boss_bars)).unwrap();
+        boss_bar(
+            bar_packet(dragon, 4, |buffer| {
+                write_var_int(buffer, 2).unwrap();
+                write_var_int(buffer, 0).unwrap();
+            }),
+            Arc::clone(&boss_bars),
+        )
+        .unwrap();
+        boss_bar(bar_packet(event, 1, |_| {}), Arc::clone(&boss_bars)).unwrap();
+
+        let boss_bars = boss_bars.lock().unwrap();
+        assert_eq!(boss_bars.bars().len(), 1);
+        let bar = boss_bars.find(dragon).unwrap();
+        assert_eq!((bar.color, bar.notches, bar.flags), ("red", 0, 0x01));
+        let line = bar.to_component();
+        assert_eq!(line.to_plain_text(), format!("{}{} Dragon 25%", "█".repeat(5), "░".repeat(15)));
+        assert_eq!(line.spans()[0].style.color, Color::parse("red"));
+    }
+
+    #[test]
+    fn unknown_actions_and_styles_are_errors() {
+        let boss_bars = Arc::new(Mutex::new(BossBars::new()));
+        assert!(boss_bar(bar_packet(Uuid(1), 6, |_| {}), Arc::clone(&boss_bars)).is_err());
+        let packet = bar_packet(Uuid(1), 4, |buffer| {
+            write_var_int(buffer, 7).unwrap();
+            write_var_int(buffer, 0).unwrap();
+        });
+        assert!(boss_bar(packet, boss_bars).is_err());
+    }
+
+    proptest! {
+        #[test]
+        fn boss_bar_never_panics(bytes: Vec<u8>) {
+            let _ = boss_bar(bytes, Arc::new(Mutex::new(BossBars::new())));
+        }
+    }
+}
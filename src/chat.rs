@@ -0,0 +1,291 @@
+use std::io;
+use std::io::Cursor;
+use std::io::{Error, ErrorKind};
+use std::sync::{Arc, Mutex};
+use serde_json::Value;
+use crate::codec::{read_byte, read_string, read_uuid};
+use crate::component::Component;
+use crate::interactions::Interactions;
+use crate::players::Players;
+use crate::text_formatting;
+use crate::uuid::Uuid;
+
+/// Where the vanilla client shows a message.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ChatPosition {
+    Chat,
+    System,
+    /// Above the hotbar, also called the action bar.
+    GameInfo,
+}
+
+impl ChatPosition {
+    pub const ALL: [ChatPosition; 3] = [ChatPosition::Chat, ChatPosition::System, ChatPosition::GameInfo];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            ChatPosition::Chat => "chat",
+            ChatPosition::System => "system",
+            ChatPosition::GameInfo => "action_bar",
+        }
+    }
+
+    pub fn parse(name: &str) -> Option<ChatPosition> {
+        ChatPosition::ALL.into_iter().find(|position| position.name() == name)
+    }
+}
+
+pub struct ChatMessage {
+    /// The component as the server sent it, for the chat log.
+    pub json: Value,
+    pub message: Component,
+    pub position: ChatPosition,
+    /// The player who sent the message, nil for messages from the server.
+    pub sender: Uuid,
+}
+
+/// What the reader and the input thread share about chat.
+pub struct Chat {
+    pub interactions: Interactions,
+    hidden: Vec<ChatPosition>,
+    action_bar: Option<String>,
+}
+
+impl Chat {
+    pub fn new(hidden: Vec<ChatPosition>) -> Chat {
+        Chat {
+            interactions: Interactions::new(),
+            hidden,
+            action_bar: None,
+        }
+    }
+
+    pub fn is_hidden(&self, position: ChatPosition) -> bool {
+        self.hidden.contains(&position)
+    }
+
+    pub fn set_hidden(&mut self, position: ChatPosition, hidden: bool) {
+        self.hidden.retain(|hidden_position| *hidden_position != position);
+        if hidden {
+            self.hidden.push(position);
+        }
+    }
+
+    /// Whether a message goes to the chat log: shown chat and system
+    /// messages, not the action bar, which servers resend all the time.
+    pub fn is_logged(&self, position: ChatPosition) -> bool {
+        position != ChatPosition::GameInfo && !self.is_hidden(position)
+    }
+
+    /// Remembers the action bar text, returns false if it is the one already
+    /// shown. Servers resend it every second or so to keep it on screen.
+    pub fn update_action_bar(&mut self, text: &str) -> bool {
+        if self.action_bar.as_deref() == Some(text) {
+            return false;
+        }
+        self.action_bar = Some(text.to_string());
+        true
+    }
+}
+
+impl Default for Chat {
+    fn default() -> Self {
+        Self::new(Vec::new())
+    }
+}
+
+pub fn read_chat_message(buffer: Vec<u8>) -> io::Result<ChatMessage> {
+    let mut reader = Cursor::new(buffer);
+    let nbt_text: String = read_string(&mut reader)?;
+    let invalid = || Error::new(ErrorKind::InvalidData, "Chat message is not a valid chat component");
+    let json: Value = serde_json::from_str(&nbt_text).map_err(|_| invalid())?;
+    let message = Component::from_json(&json).map_err(|_| invalid())?;
+    let position = match read_byte(&mut reader)? {
+        0 => ChatPosition::Chat,
+        1 => ChatPosition::System,
+        2 => ChatPosition::GameInfo,
+        _ => return Err(Error::new(ErrorKind::InvalidData, "Unknown chat position")),
+    };
+    let sender = read_uuid(&mut reader)?;
+    Ok(ChatMessage { json, message, position, sender })
+}
+
+pub fn chat_from_server(buffer: Vec<u8>, chat: Arc<Mutex<Chat>>, all_players: Arc<Mutex<Players>>) -> io::Result<ChatMessage> {
+    let chat_message = read_chat_message(buffer)?;
+    let mut chat = chat.lock().unwrap();
+    if chat.is_hidden(chat_message.position) {
+        return Ok(chat_message);
+    }
+
+    if chat_message.position == ChatPosition::GameInfo {
+        show_action_bar(&mut chat, &chat_message.message);
+        return Ok(chat_message);
+    }
+
+    let text = text_formatting::to_ansi_numbered(&chat_message.message, &mut chat.interactions);
+
+    // Name the sender when the message itself does not, as plugins often
+    // send player chat without the usual `<name>`.
+    let plain_text = chat_message.message.to_plain_text();
+    let sender = all_players.lock().unwrap().find_by_uuid(chat_message.sender).map(|player| player.username().to_string());
+    match sender {
+        Some(sender) if chat_message.sender != Uuid::default() && !plain_text.contains(&sender) => {
+            println!("[{}] {}", sender, text);
+        }
+        _ => println!("{}", text),
+    }
+    Ok(chat_message)
+}
+
+/// Prints an action bar unless it is the one already shown. Interactions are
+/// only numbered once it is printed, so a bar resent every tick does not
+/// push chat messages out of the interaction buffer.
+fn show_action_bar(chat: &mut Chat, message: &Component) {
+    if chat.update_action_bar(&text_formatting::to_ansi(message)) {
+        println!("[Action bar] {}", text_formatting::to_ansi_numbered(message, &mut chat.interactions));
+    }
+}
+
+/// The Set Action Bar Text packet, which newer servers use instead of a
+/// chat message in the game info position.
+pub fn action_bar(buffer: Vec<u8>, chat: Arc<Mutex<Chat>>) -> io::Result<Component> {
+    let mut reader = Cursor::new(buffer);
+    let json = read_string(&mut reader)?;
+    let message: Component =
+        serde_json::from_str(&json).map_err(|_| Error::new(ErrorKind::InvalidData, "Action bar is not a valid chat component"))?;
+    let mut chat = chat.lock().unwrap();
+    if !chat.is_hidden(ChatPosition::GameInfo) {
+        show_action_bar(&mut chat, &message);
+    }
+    Ok(message)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::codec::write_string;
+    use proptest::prelude::*;
+
+    fn chat() -> Arc<Mutex<Chat>> {
+        Arc::new(Mutex::new(Chat::default()))
+    }
+
+    fn players() -> Arc<Mutex<Players>> {
+        Arc::new(Mutex::new(Players::new()))
+    }
+
+    fn message_packet(json: &str, position: u8, sender: u8) -> Vec<u8> {
+        let mut buffer: Vec<u8> = Vec::new();
+        write_string(&mut buffer, json).unwrap();
+        buffer.push(position);
+        buffer.extend_from_slice(&[sender; 16]);
+        buffer
+    }
+
+    fn chat_packet(json: &str) -> Vec<u8> {
+        message_packet(json, 0, 0)
+    }
+
+    #[test]
+    fn every_json_shape_is_accepted() {
+        chat_from_server(chat_packet(r#"{"text":"hi","color":"red","bold":true}"#), chat(), players()).unwrap();
+        chat_from_server(chat_packet(r#"["a",{"text":"b"},["c"]]"#), chat(), players()).unwrap();
+        chat_from_server(chat_packet(r#""plain""#), chat(), players()).unwrap();
+    }
+
+    #[test]
+    fn malformed_styles_do_not_panic() {
+        chat_from_server(chat_packet(r#"{"text":"x","color":5,"bold":"yes"}"#), chat(), players()).unwrap();
+        chat_from_server(chat_packet(r##"{"text":"x","color":"#ééé"}"##), chat(), players()).unwrap();
+        chat_from_server(chat_packet(r##"{"text":"x","color":"#zzzzzz"}"##), chat(), players()).unwrap();
+    }
+
+    #[test]
+    fn malformed_packets_are_errors() {
+        assert!(chat_from_server(Vec::new(), chat(), players()).is_err());
+        assert!(chat_from_server(chat_packet("{"), chat(), players()).is_err());
+        assert!(chat_from_server(vec![0x05, b'{'], chat(), players()).is_err());
+        assert!(chat_from_server(message_packet("\"x\"", 3, 0), chat(), players()).is_err());
+        let mut truncated = chat_packet("\"x\"");
+        truncated.pop();
+        assert!(chat_from_server(truncated, chat(), players()).is_err());
+    }
+
+    #[test]
+    fn clickable_parts_are_numbered() {
+        let chat = chat();
+        let json = r#"{"text":"Rules: ","extra":[
+            {"text":"site","clickEvent":{"action":"open_url","value":"https://example.com"}},
+            {"text":" or "},
+            {"text":"/rules","clickEvent":{"action":"run_command","value":"/rules"},"extra":[{"text":"!","bold":true}]}]}"#;
+        chat_from_server(chat_packet(json), Arc::clone(&chat), players()).unwrap();
+        let chat = chat.lock().unwrap();
+        let all_interactions = &chat.interactions;
+        let rules = all_interactions.get(2).unwrap().click_event.as_ref().unwrap();
+        assert_eq!(rules.value, "/rules");
+        assert!(all_interactions.get(3).is_none());
+    }
+
+    #[test]
+    fn position_and_sender_are_read() {
+        let message = read_chat_message(message_packet("\"hi\"", 2, 7)).unwrap();
+        assert_eq!(message.position, ChatPosition::GameInfo);
+        assert_eq!(message.sender, Uuid(u128::from_be_bytes([7; 16])));
+        assert_eq!(read_chat_message(message_packet("\"hi\"", 1, 0)).unwrap().position, ChatPosition::System);
+    }
+
+    #[test]
+    fn action_bar_repeats_and_hidden_types() {
+        let mut chat = Chat::new(vec![ChatPosition::System]);
+        assert!(chat.update_action_bar("Health: 20"));
+        assert!(!chat.update_action_bar("Health: 20"));
+        assert!(chat.update_action_bar("Health: 19"));
+
+        assert!(chat.is_hidden(ChatPosition::System));
+        chat.set_hidden(ChatPosition::System, false);
+        chat.set_hidden(ChatPosition::GameInfo, true);
+        chat.set_hidden(ChatPosition::GameInfo, true);
+        assert!(!chat.is_hidden(ChatPosition::System));
+        assert_eq!(chat.hidden, [ChatPosition::GameInfo]);
+        assert_eq!(ChatPosition::parse("action_bar"), Some(ChatPosition::GameInfo));
+        assert!(chat.is_logged(ChatPosition::System));
+        assert!(!chat.is_logged(ChatPosition::GameInfo));
+        chat.set_hidden(ChatPosition::Chat, true);
+        assert!(!chat.is_logged(ChatPosition::Chat));
+    }
+
+    #[test]
+    fn action_bar_packet_shares_the_repeat_check() {
+        let chat = chat();
+        let mut buffer: Vec<u8> = Vec::new();
+        write_string(&mut buffer, r#"{"text":"Health: 20"}"#).unwrap();
+        assert_eq!(action_bar(buffer.clone(), Arc::clone(&chat)).unwrap().to_plain_text(), "Health: 20");
+        assert!(!chat.lock().unwrap().update_action_bar("Health: 20"));
+        assert!(action_bar(vec![0x01, b'{'], chat).is_err());
+    }
+
+    #[test]
+    fn repeated_action_bar_numbers_interactions_once() {
+        let chat = chat();
+        let mut buffer: Vec<u8> = Vec::new();
+        write_string(&mut buffer, r#"{"text":"Shop","clickEvent":{"action":"run_command","value":"/shop"}}"#).unwrap();
+        for _ in 0..3 {
+            action_bar(buffer.clone(), Arc::clone(&chat)).unwrap();
+        }
+        let chat = chat.lock().unwrap();
+        assert!(chat.interactions.get(1).is_some());
+        assert!(chat.interactions.get(2).is_none());
+    }
+
+    proptest! {
+        #[test]
+        fn chat_from_server_never_panics(bytes: Vec<u8>) {
+            let _ = chat_from_server(bytes, chat(), players());
+        }
+
+        #[test]
+        fn chat_json_never_panics(json in "[\\[\\]{}\":,a-z#0-9 ]{0,64}") {
+            let _ = chat_from_server(chat_packet(&json), chat(), players());
+        }
+    }
+}
@@ -0,0 +1,101 @@
+use std::fs::{File, OpenOptions};
+use std::io;
+use std::io::{BufRead, BufReader, Write};
+use serde_json::{json, Value};
+use crate::component::Component;
+use crate::html::html_document;
+use crate::time::format_time;
+
+/// A file the received chat messages are appended to, one JSON object with
+/// the time and the original chat component per line.
+pub struct ChatLog {
+    file: File,
+}
+
+impl ChatLog {
+    pub fn open(path: &str) -> io::Result<ChatLog> {
+        Ok(ChatLog {
+            file: OpenOptions::new().create(true).append(true).open(path)?,
+        })
+    }
+
+    pub fn append(&mut self, time: u64, message: &Value) -> io::Result<()> {
+        writeln!(self.file, "{}", json!({"time": time, "message": message}))
+    }
+}
+
+pub struct LogEntry {
+    pub time: u64,
+    pub message: Component,
+}
+
+/// Reads a chat log. Lines that can not be read, like one cut short when the
+/// client was killed, are skipped.
+pub fn read_chat_log(path: &str) -> io::Result<Vec<LogEntry>> {
+    let mut entries = Vec::<LogEntry>::new();
+    for line in BufReader::new(File::open(path)?).lines() {
+        let Ok(entry) = serde_json::from_str::<Value>(&line?) else {
+            continue;
+        };
+        let time = entry.get("time").and_then(Value::as_u64);
+        let message = entry.get("message").map(Component::from_json);
+        if let (Some(time), Some(Ok(message))) = (time, message) {
+            entries.push(LogEntry { time, message });
+        }
+    }
+    Ok(entries)
+}
+
+/// Converts a chat log to a self-contained HTML page, or to plain text if the
+/// output file does not end in `.html`. Returns how many messages it wrote.
+pub fn export(log_path: &str, output_path: &str) -> io::Result<usize> {
+    let entries = read_chat_log(log_path)?;
+    let lines: Vec<(String, Component)> = entries
+        .into_iter()
+        .map(|entry| (format_time(entry.time), entry.message))
+        .collect();
+
+    let mut output = File::create(output_path)?;
+    if output_path.ends_with(".html") || output_path.ends_with(".htm") {
+        output.write_all(html_document(log_path, &lines).as_bytes())?;
+    } else {
+        for (time, message) in &lines {
+            writeln!(output, "[{}] {}", time, message.to_plain_text())?;
+        }
+    }
+    Ok(lines.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+    use std::fs;
+
+    #[test]
+    fn log_is_exported_as_text_and_html() {
+        let directory = env::temp_dir();
+        let log_path = directory.join(format!("chat_log_test_{}.jsonl", std::process::id()));
+        let log_path = log_path.to_str().unwrap();
+        let text_path = format!("{}.txt", log_path);
+        let html_path = format!("{}.html", log_path);
+
+        let mut log = ChatLog::open(log_path).unwrap();
+        log.append(0, &json!({"translate": "chat.type.text", "with": ["Steve", "hi"]})).unwrap();
+        log.append(60, &json!({"text": "<bold>", "bold": true})).unwrap();
+        drop(log);
+        fs::OpenOptions::new().append(true).open(log_path).unwrap().write_all(b"{\"time\": 1").unwrap();
+
+        assert_eq!(export(log_path, &text_path).unwrap(), 2);
+        assert_eq!(
+            fs::read_to_string(&text_path).unwrap(),
+            "[1970-01-01 00:00:00] <Steve> hi\n[1970-01-01 00:01:00] <bold>\n"
+        );
+        assert_eq!(export(log_path, &html_path).unwrap(), 2);
+        assert!(fs::read_to_string(&html_path).unwrap().contains("font-weight:bold\">&lt;bold&gt;</span>"));
+
+        for path in [log_path, &text_path, &html_path] {
+            fs::remove_file(path).unwrap();
+        }
+    }
+}
@@ -0,0 +1,58 @@
+use std::io;
+use crate::codec::{write_bool, write_string, write_var_int};
+use crate::config::{ChatMode, Config};
+
+/// Every part of the skin shown: cape, jacket, sleeves, pants and hat.
+pub const ALL_SKIN_PARTS: u8 = 0x7F;
+pub const RIGHT_HAND: i32 = 1;
+pub const BRAND_CHANNEL: &str = "minecraft:brand";
+
+/// The fields of the Client Settings packet, from the config.
+pub fn write_client_settings(buffer: &mut Vec<u8>, config: &Config) -> io::Result<()> {
+    write_string(buffer, &config.locale)?;
+    buffer.push(config.view_distance);
+    let chat_mode = match config.chat_mode {
+        ChatMode::Full => 0,
+        ChatMode::CommandsOnly => 1,
+        ChatMode::Hidden => 2,
+    };
+    write_var_int(buffer, chat_mode)?;
+    write_bool(buffer, config.chat_colors)?;
+    buffer.push(ALL_SKIN_PARTS);
+    write_var_int(buffer, RIGHT_HAND)?;
+    // No text filtering, and the player may show up in the server list.
+    write_bool(buffer, false)?;
+    write_bool(buffer, true)
+}
+
+/// The fields of the Plugin Message that tells the server the client brand.
+pub fn write_brand(buffer: &mut Vec<u8>, brand: &str) -> io::Result<()> {
+    write_string(buffer, BRAND_CHANNEL)?;
+    write_string(buffer, brand)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn settings_follow_the_config() {
+        let config = Config {
+            locale: "ro_ro".to_string(),
+            view_distance: 4,
+            chat_mode: ChatMode::CommandsOnly,
+            chat_colors: false,
+            ..Config::default()
+        };
+        let mut buffer: Vec<u8> = Vec::new();
+        write_client_settings(&mut buffer, &config).unwrap();
+        assert_eq!(buffer, [&[5][..], b"ro_ro", &[4, 1, 0, 0x7F, 1, 0, 1]].concat());
+    }
+
+    #[test]
+    fn brand_is_a_string_on_the_brand_channel() {
+        let mut buffer: Vec<u8> = Vec::new();
+        write_brand(&mut buffer, "vanilla").unwrap();
+        assert_eq!(buffer, [&[15][..], b"minecraft:brand", &[7], b"vanilla"].concat());
+    }
+}
@@ -0,0 +1,444 @@
+use std::io;
+use std::io::Read;
+use std::io::{Error, ErrorKind};
+use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
+use crate::uuid::Uuid;
+
+pub mod nbt;
+
+const SEGMENT_BITS: u8 = 0b0111_1111;
+const CONTINUE_BIT: u8 = 0b1000_0000;
+
+// Strings sent by the server are at most 32767 UTF-16 code units, which is
+// at most this many UTF-8 bytes.
+const MAX_STRING_LENGTH: i32 = 32767 * 4;
+
+pub fn write_var_int(buffer: &mut Vec<u8>, mut value: i32) -> io::Result<()> {
+    loop {
+        if (value & !(SEGMENT_BITS as i32)) == 0 {
+            buffer.push(value as u8);
+            return Ok(());
+        }
+
+        buffer.push((value & SEGMENT_BITS as i32) as u8 | CONTINUE_BIT);
+        value = ((value as u32) >> 7) as i32;
+    }
+}
+
+/// Reads a VarInt from the start of `buffer` and returns the value followed
+/// by the number of bytes it took up.
+pub fn read_var_int(buffer: &[u8]) -> io::Result<Vec<i32>> {
+    let mut value: i32 = 0;
+    let mut position: i32 = 0;
+
+    for (i, current_byte) in buffer.iter().enumerate() {
+        value |= ((current_byte & SEGMENT_BITS) as i32) << position;
+
+        if (current_byte & CONTINUE_BIT) == 0 {
+            return Ok(vec![value, i as i32 + 1]);
+        }
+
+        position += 7;
+
+        if position >= 32 {
+            return Err(Error::new(ErrorKind::InvalidData, "VarInt is too big"));
+        }
+    }
+
+    Err(Error::new(ErrorKind::UnexpectedEof, "VarInt ends before its last byte"))
+}
+
+pub fn read_var_int_from_stream<R: Read>(stream: &mut R) -> io::Result<i32> {
+    let mut value: i32 = 0;
+    let mut position: i32 = 0;
+    let mut current_byte: [u8; 1] = [0];
+
+    loop {
+        stream.read_exact(&mut current_byte)?;
+        value |= ((current_byte[0] & SEGMENT_BITS) as i32) << position;
+
+        if (current_byte[0] & CONTINUE_BIT) == 0 {
+            break;
+        }
+
+        position += 7;
+
+        if position >= 32 {
+            return Err(Error::new(ErrorKind::InvalidData, "VarInt is too big"));
+        }
+    }
+
+    Ok(value)
+}
+
+pub fn write_var_long(buffer: &mut Vec<u8>, mut value: i64) -> io::Result<()> {
+    loop {
+        if (value & !(SEGMENT_BITS as i64)) == 0 {
+            buffer.push(value as u8);
+            return Ok(());
+        }
+
+        buffer.push((value & SEGMENT_BITS as i64) as u8 | CONTINUE_BIT);
+        value = ((value as u64) >> 7) as i64;
+    }
+}
+
+/// Reads a VarLong from the start of `buffer` and returns the value followed
+/// by the number of bytes it took up.
+pub fn read_var_long(buffer: &[u8]) -> io::Result<Vec<i64>> {
+    let mut value: i64 = 0;
+    let mut position: i64 = 0;
+
+    for (i, current_byte) in buffer.iter().enumerate() {
+        value |= ((current_byte & SEGMENT_BITS) as i64) << position;
+
+        if (current_byte & CONTINUE_BIT) == 0 {
+            return Ok(vec![value, i as i64 + 1]);
+        }
+
+        position += 7;
+
+        if position >= 64 {
+            return Err(Error::new(ErrorKind::InvalidData, "VarLong is too big"));
+        }
+    }
+
+    Err(Error::new(ErrorKind::UnexpectedEof, "VarLong ends before its last byte"))
+}
+
+pub fn read_var_long_from_stream<R: Read>(stream: &mut R) -> io::Result<i64> {
+    let mut value: i64 = 0;
+    let mut position: i64 = 0;
+
+    loop {
+        let current_byte: u8 = read_byte(stream)?;
+        value |= ((current_byte & SEGMENT_BITS) as i64) << position;
+
+        if (current_byte & CONTINUE_BIT) == 0 {
+            break;
+        }
+
+        position += 7;
+
+        if position >= 64 {
+            return Err(Error::new(ErrorKind::InvalidData, "VarLong is too big"));
+        }
+    }
+
+    Ok(value)
+}
+
+pub fn write_string(buffer: &mut Vec<u8>, value: &str) -> io::Result<()> {
+    write_var_int(buffer, value.len() as i32)?;
+    buffer.extend_from_slice(value.as_bytes());
+    Ok(())
+}
+
+pub fn read_string<R: Read>(stream: &mut R) -> io::Result<String> {
+    let size: i32 = read_var_int_from_stream(stream)?;
+    if !(0..=MAX_STRING_LENGTH).contains(&size) {
+        return Err(Error::new(ErrorKind::InvalidData, "Invalid string length"));
+    }
+    let mut buffer: Vec<u8> = vec![0; size as usize];
+    stream.read_exact(&mut buffer)?;
+    String::from_utf8(buffer).map_err(|_| Error::new(ErrorKind::InvalidData, "String is not valid UTF-8"))
+}
+
+pub fn write_long(buffer: &mut Vec<u8>, value: i64) -> io::Result<()> {
+    buffer.write_i64::<BigEndian>(value)
+}
+
+pub fn read_long<R: Read>(stream: &mut R) -> io::Result<i64> {
+    stream.read_i64::<BigEndian>()
+}
+
+pub fn write_int(buffer: &mut Vec<u8>, value: i32) -> io::Result<()> {
+    buffer.write_i32::<BigEndian>(value)
+}
+
+pub fn read_int<R: Read>(stream: &mut R) -> io::Result<i32> {
+    stream.read_i32::<BigEndian>()
+}
+
+pub fn write_short(buffer: &mut Vec<u8>, value: i16) -> io::Result<()> {
+    buffer.write_i16::<BigEndian>(value)
+}
+
+pub fn read_short<R: Read>(stream: &mut R) -> io::Result<i16> {
+    stream.read_i16::<BigEndian>()
+}
+
+pub fn write_float(buffer: &mut Vec<u8>, value: f32) -> io::Result<()> {
+    buffer.write_f32::<BigEndian>(value)
+}
+
+pub fn read_float<R: Read>(stream: &mut R) -> io::Result<f32> {
+    stream.read_f32::<BigEndian>()
+}
+
+pub fn write_double(buffer: &mut Vec<u8>, value: f64) -> io::Result<()> {
+    buffer.write_f64::<BigEndian>(value)
+}
+
+pub fn read_double<R: Read>(stream: &mut R) -> io::Result<f64> {
+    stream.read_f64::<BigEndian>()
+}
+
+pub fn read_byte<R: Read>(stream: &mut R) -> io::Result<u8> {
+    stream.read_u8()
+}
+
+pub fn write_bool(buffer: &mut Vec<u8>, value: bool) -> io::Result<()> {
+    buffer.push(value as u8);
+    Ok(())
+}
+
+pub fn read_bool<R: Read>(stream: &mut R) -> io::Result<bool> {
+    Ok(read_byte(stream)? != 0)
+}
+
+pub fn write_uuid(buffer: &mut Vec<u8>, value: Uuid) -> io::Result<()> {
+    buffer.write_u128::<BigEndian>(value.0)
+}
+
+pub fn read_uuid<R: Read>(stream: &mut R) -> io::Result<Uuid> {
+    Ok(Uuid(stream.read_u128::<BigEndian>()?))
+}
+
+/// A block position, packed on the wire as x (26 bits), z (26 bits) and
+/// y (12 bits) in one Long.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Position {
+    pub x: i32,
+    pub y: i32,
+    pub z: i32,
+}
+
+pub fn write_position(buffer: &mut Vec<u8>, value: Position) -> io::Result<()> {
+    let packed: i64 = ((value.x as i64 & 0x3FF_FFFF) << 38)
+        | ((value.z as i64 & 0x3FF_FFFF) << 12)
+        | (value.y as i64 & 0xFFF);
+    write_long(buffer, packed)
+}
+
+pub fn read_position<R: Read>(stream: &mut R) -> io::Result<Position> {
+    let packed: i64 = read_long(stream)?;
+    Ok(Position {
+        x: (packed >> 38) as i32,
+        y: (packed << 52 >> 52) as i32,
+        z: (packed << 26 >> 38) as i32,
+    })
+}
+
+/// Writes a rotation in degrees as an Angle, which has 256 steps per turn.
+pub fn write_angle(buffer: &mut Vec<u8>, degrees: f32) -> io::Result<()> {
+    buffer.push((degrees.rem_euclid(360.0) * 256.0 / 360.0).round() as i32 as u8);
+    Ok(())
+}
+
+pub fn read_angle<R: Read>(stream: &mut R) -> io::Result<f32> {
+    Ok(read_byte(stream)? as f32 * 360.0 / 256.0)
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct BitSet {
+    pub words: Vec<u64>,
+}
+
+impl BitSet {
+    pub fn get(&self, index: usize) -> bool {
+        self.words
+            .get(index / 64)
+            .is_some_and(|word| word & (1 << (index % 64)) != 0)
+    }
+
+    pub fn set(&mut self, index: usize, value: bool) {
+        if index / 64 >= self.words.len() {
+            self.words.resize(index / 64 + 1, 0);
+        }
+        if value {
+            self.words[index / 64] |= 1 << (index % 64);
+        } else {
+            self.words[index / 64] &= !(1 << (index % 64));
+        }
+    }
+}
+
+pub fn write_bit_set(buffer: &mut Vec<u8>, value: &BitSet) -> io::Result<()> {
+    write_var_int(buffer, value.words.len() as i32)?;
+    for word in &value.words {
+        buffer.write_u64::<BigEndian>(*word)?;
+    }
+    Ok(())
+}
+
+pub fn read_bit_set<R: Read>(stream: &mut R) -> io::Result<BitSet> {
+    let length: i32 = read_var_int_from_stream(stream)?;
+    if length < 0 {
+        return Err(Error::new(ErrorKind::InvalidData, "Invalid BitSet length"));
+    }
+    let mut words: Vec<u64> = Vec::new();
+    for _ in 0..length {
+        words.push(stream.read_u64::<BigEndian>()?);
+    }
+    Ok(BitSet { words })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+    use std::io::Cursor;
+
+    #[test]
+    fn var_int_known_values() {
+        let cases: [(i32, &[u8]); 7] = [
+            (0, &[0x00]),
+            (1, &[0x01]),
+            (127, &[0x7f]),
+            (128, &[0x80, 0x01]),
+            (25565, &[0xdd, 0xc7, 0x01]),
+            (i32::MAX, &[0xff, 0xff, 0xff, 0xff, 0x07]),
+            (-1, &[0xff, 0xff, 0xff, 0xff, 0x0f]),
+        ];
+        for (value, bytes) in cases {
+            let mut buffer: Vec<u8> = Vec::new();
+            write_var_int(&mut buffer, value).unwrap();
+            assert_eq!(buffer, bytes);
+            assert_eq!(read_var_int(bytes).unwrap(), vec![value, bytes.len() as i32]);
+        }
+    }
+
+    #[test]
+    fn var_int_truncated_is_an_error() {
+        assert!(read_var_int(&[]).is_err());
+        assert!(read_var_int(&[0x80]).is_err());
+        assert!(read_var_int(&[0xff, 0xff]).is_err());
+        assert!(read_var_int_from_stream(&mut Cursor::new([0x80])).is_err());
+    }
+
+    #[test]
+    fn var_int_too_long_is_an_error() {
+        assert!(read_var_int(&[0xff, 0xff, 0xff, 0xff, 0xff, 0x01]).is_err());
+        assert!(read_var_int_from_stream(&mut Cursor::new([0xff, 0xff, 0xff, 0xff, 0xff, 0x01])).is_err());
+    }
+
+    #[test]
+    fn position_known_value() {
+        // Example from the protocol documentation.
+        let mut buffer: Vec<u8> = Vec::new();
+        write_long(&mut buffer, 0b01000110_00000111_01100011_00101100_00010101_10110100_10000011_00111111).unwrap();
+        let position = read_position(&mut Cursor::new(&buffer)).unwrap();
+        assert_eq!(position, Position { x: 18357644, y: 831, z: -20882616 });
+    }
+
+    #[test]
+    fn angle_wraps_around() {
+        let mut buffer: Vec<u8> = Vec::new();
+        write_angle(&mut buffer, -90.0).unwrap();
+        write_angle(&mut buffer, 450.0).unwrap();
+        assert_eq!(buffer, vec![192, 64]);
+    }
+
+    #[test]
+    fn var_long_known_values() {
+        let cases: [(i64, &[u8]); 4] = [
+            (0, &[0x00]),
+            (2147483648, &[0x80, 0x80, 0x80, 0x80, 0x08]),
+            (i64::MAX, &[0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x7f]),
+            (-1, &[0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x01]),
+        ];
+        for (value, bytes) in cases {
+            let mut buffer: Vec<u8> = Vec::new();
+            write_var_long(&mut buffer, value).unwrap();
+            assert_eq!(buffer, bytes);
+            assert_eq!(read_var_long(bytes).unwrap(), vec![value, bytes.len() as i64]);
+        }
+        assert!(read_var_long(&[0x80]).is_err());
+    }
+
+    proptest! {
+        #[test]
+        fn var_int_round_trip(value: i32, trailing: Vec<u8>) {
+            let mut buffer: Vec<u8> = Vec::new();
+            write_var_int(&mut buffer, value).unwrap();
+            let length = buffer.len() as i32;
+            prop_assert!(length <= 5);
+            buffer.extend_from_slice(&trailing);
+            prop_assert_eq!(read_var_int(&buffer).unwrap(), vec![value, length]);
+            let mut stream = Cursor::new(&buffer);
+            prop_assert_eq!(read_var_int_from_stream(&mut stream).unwrap(), value);
+            prop_assert_eq!(stream.position(), length as u64);
+        }
+
+        #[test]
+        fn var_long_round_trip(value: i64, trailing: Vec<u8>) {
+            let mut buffer: Vec<u8> = Vec::new();
+            write_var_long(&mut buffer, value).unwrap();
+            let length = buffer.len() as i64;
+            prop_assert!(length <= 10);
+            buffer.extend_from_slice(&trailing);
+            prop_assert_eq!(read_var_long(&buffer).unwrap(), vec![value, length]);
+        }
+
+        #[test]
+        fn var_int_never_panics(bytes: Vec<u8>) {
+            let _ = read_var_int(&bytes);
+            let _ = read_var_long(&bytes);
+            let _ = read_var_int_from_stream(&mut Cursor::new(&bytes));
+        }
+
+        #[test]
+        fn var_long_stream_round_trip(value: i64) {
+            let mut buffer: Vec<u8> = Vec::new();
+            write_var_long(&mut buffer, value).unwrap();
+            prop_assert_eq!(read_var_long_from_stream(&mut Cursor::new(&buffer)).unwrap(), value);
+        }
+
+        #[test]
+        fn position_round_trip(x in -(1 << 25)..(1 << 25), y in -2048..2048, z in -(1 << 25)..(1 << 25)) {
+            let position = Position { x, y, z };
+            let mut buffer: Vec<u8> = Vec::new();
+            write_position(&mut buffer, position).unwrap();
+            prop_assert_eq!(read_position(&mut Cursor::new(&buffer)).unwrap(), position);
+        }
+
+        #[test]
+        fn angle_round_trip(step: u8) {
+            let mut buffer: Vec<u8> = vec![step];
+            let degrees = read_angle(&mut Cursor::new(&buffer)).unwrap();
+            buffer.clear();
+            write_angle(&mut buffer, degrees).unwrap();
+            prop_assert_eq!(buffer, vec![step]);
+        }
+
+        #[test]
+        fn uuid_round_trip(value: u128) {
+            let mut buffer: Vec<u8> = Vec::new();
+            write_uuid(&mut buffer, Uuid(value)).unwrap();
+            prop_assert_eq!(buffer.len(), 16);
+            prop_assert_eq!(read_uuid(&mut Cursor::new(&buffer)).unwrap(), Uuid(value));
+        }
+
+        #[test]
+        fn bit_set_round_trip(bits in proptest::collection::vec(0usize..512, 0..32)) {
+            let mut bit_set = BitSet::default();
+            for bit in &bits {
+                bit_set.set(*bit, true);
+            }
+            let mut buffer: Vec<u8> = Vec::new();
+            write_bit_set(&mut buffer, &bit_set).unwrap();
+            let read = read_bit_set(&mut Cursor::new(&buffer)).unwrap();
+            for bit in 0..512 {
+                prop_assert_eq!(read.get(bit), bits.contains(&bit));
+            }
+        }
+
+        #[test]
+        fn string_round_trip(value: String) {
+            let mut buffer: Vec<u8> = Vec::new();
+            write_string(&mut buffer, &value).unwrap();
+            prop_assert_eq!(read_string(&mut Cursor::new(&buffer)).unwrap(), value);
+        }
+    }
+}
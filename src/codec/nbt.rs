@@ -0,0 +1,357 @@
+use std::io;
+use std::io::Read;
+use std::io::{Error, ErrorKind};
+use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
+
+// Same nesting limit the vanilla client uses.
+const MAX_DEPTH: usize = 512;
+
+const TAG_END: u8 = 0;
+const TAG_BYTE: u8 = 1;
+const TAG_SHORT: u8 = 2;
+const TAG_INT: u8 = 3;
+const TAG_LONG: u8 = 4;
+const TAG_FLOAT: u8 = 5;
+const TAG_DOUBLE: u8 = 6;
+const TAG_BYTE_ARRAY: u8 = 7;
+const TAG_STRING: u8 = 8;
+const TAG_LIST: u8 = 9;
+const TAG_COMPOUND: u8 = 10;
+const TAG_INT_ARRAY: u8 = 11;
+const TAG_LONG_ARRAY: u8 = 12;
+
+/// A binary NBT value. Compounds keep their entries in the order they were
+/// read so that writing a tag back produces the same bytes.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Tag {
+    End,
+    Byte(i8),
+    Short(i16),
+    Int(i32),
+    Long(i64),
+    Float(f32),
+    Double(f64),
+    ByteArray(Vec<i8>),
+    String(String),
+    List(Vec<Tag>),
+    Compound(Vec<(String, Tag)>),
+    IntArray(Vec<i32>),
+    LongArray(Vec<i64>),
+}
+
+impl Tag {
+    fn id(&self) -> u8 {
+        match self {
+            Tag::End => TAG_END,
+            Tag::Byte(_) => TAG_BYTE,
+            Tag::Short(_) => TAG_SHORT,
+            Tag::Int(_) => TAG_INT,
+            Tag::Long(_) => TAG_LONG,
+            Tag::Float(_) => TAG_FLOAT,
+            Tag::Double(_) => TAG_DOUBLE,
+            Tag::ByteArray(_) => TAG_BYTE_ARRAY,
+            Tag::String(_) => TAG_STRING,
+            Tag::List(_) => TAG_LIST,
+            Tag::Compound(_) => TAG_COMPOUND,
+            Tag::IntArray(_) => TAG_INT_ARRAY,
+            Tag::LongArray(_) => TAG_LONG_ARRAY,
+        }
+    }
+
+    /// Looks up an entry of a compound tag by name.
+    pub fn get(&self, name: &str) -> Option<&Tag> {
+        match self {
+            Tag::Compound(entries) => entries.iter().find(|(key, _)| key == name).map(|(_, tag)| tag),
+            _ => None,
+        }
+    }
+}
+
+/// Reads a named root tag. A root `TAG_End` stands for "no NBT" and is
+/// returned as `Tag::End` with an empty name.
+pub fn read_named_tag<R: Read>(stream: &mut R) -> io::Result<(String, Tag)> {
+    let id: u8 = stream.read_u8()?;
+    if id == TAG_END {
+        return Ok((String::new(), Tag::End));
+    }
+    let name: String = read_nbt_string(stream)?;
+    let tag: Tag = read_payload(stream, id, 0)?;
+    Ok((name, tag))
+}
+
+pub fn write_named_tag(buffer: &mut Vec<u8>, name: &str, tag: &Tag) -> io::Result<()> {
+    buffer.push(tag.id());
+    if let Tag::End = tag {
+        return Ok(());
+    }
+    write_nbt_string(buffer, name)?;
+    write_payload(buffer, tag)
+}
+
+fn read_length<R: Read>(stream: &mut R) -> io::Result<usize> {
+    let length: i32 = stream.read_i32::<BigEndian>()?;
+    if length < 0 {
+        return Err(Error::new(ErrorKind::InvalidData, "Negative NBT length"));
+    }
+    Ok(length as usize)
+}
+
+fn read_payload<R: Read>(stream: &mut R, id: u8, depth: usize) -> io::Result<Tag> {
+    if depth > MAX_DEPTH {
+        return Err(Error::new(ErrorKind::InvalidData, "NBT is nested too deeply"));
+    }
+    let tag: Tag = match id {
+        TAG_BYTE => Tag::Byte(stream.read_i8()?),
+        TAG_SHORT => Tag::Short(stream.read_i16::<BigEndian>()?),
+        TAG_INT => Tag::Int(stream.read_i32::<BigEndian>()?),
+        TAG_LONG => Tag::Long(stream.read_i64::<BigEndian>()?),
+        TAG_FLOAT => Tag::Float(stream.read_f32::<BigEndian>()?),
+        TAG_DOUBLE => Tag::Double(stream.read_f64::<BigEndian>()?),
+        TAG_BYTE_ARRAY => {
+            let length: usize = read_length(stream)?;
+            let mut bytes: Vec<u8> = Vec::new();
+            stream.take(length as u64).read_to_end(&mut bytes)?;
+            if bytes.len() != length {
+                return Err(Error::new(ErrorKind::UnexpectedEof, "NBT byte array ends early"));
+            }
+            Tag::ByteArray(bytes.into_iter().map(|byte| byte as i8).collect())
+        }
+        TAG_STRING => Tag::String(read_nbt_string(stream)?),
+        TAG_LIST => {
+            let element_id: u8 = stream.read_u8()?;
+            let length: usize = read_length(stream)?;
+            if element_id == TAG_END && length > 0 {
+                return Err(Error::new(ErrorKind::InvalidData, "NBT list of TAG_End"));
+            }
+            let mut elements: Vec<Tag> = Vec::new();
+            for _ in 0..length {
+                elements.push(read_payload(stream, element_id, depth + 1)?);
+            }
+            Tag::List(elements)
+        }
+        TAG_COMPOUND => {
+            let mut entries: Vec<(String, Tag)> = Vec::new();
+            loop {
+                let entry_id: u8 = stream.read_u8()?;
+                if entry_id == TAG_END {
+                    break;
+                }
+                let name: String = read_nbt_string(stream)?;
+                entries.push((name, read_payload(stream, entry_id, depth + 1)?));
+            }
+            Tag::Compound(entries)
+        }
+        TAG_INT_ARRAY => {
+            let length: usize = read_length(stream)?;
+            let mut values: Vec<i32> = Vec::new();
+            for _ in 0..length {
+                values.push(stream.read_i32::<BigEndian>()?);
+            }
+            Tag::IntArray(values)
+        }
+        TAG_LONG_ARRAY => {
+            let length: usize = read_length(stream)?;
+            let mut values: Vec<i64> = Vec::new();
+            for _ in 0..length {
+                values.push(stream.read_i64::<BigEndian>()?);
+            }
+            Tag::LongArray(values)
+        }
+        _ => return Err(Error::new(ErrorKind::InvalidData, "Unknown NBT tag type")),
+    };
+    Ok(tag)
+}
+
+fn write_payload(buffer: &mut Vec<u8>, tag: &Tag) -> io::Result<()> {
+    match tag {
+        Tag::End => {}
+        Tag::Byte(value) => buffer.write_i8(*value)?,
+        Tag::Short(value) => buffer.write_i16::<BigEndian>(*value)?,
+        Tag::Int(value) => buffer.write_i32::<BigEndian>(*value)?,
+        Tag::Long(value) => buffer.write_i64::<BigEndian>(*value)?,
+        Tag::Float(value) => buffer.write_f32::<BigEndian>(*value)?,
+        Tag::Double(value) => buffer.write_f64::<BigEndian>(*value)?,
+        Tag::ByteArray(values) => {
+            buffer.write_i32::<BigEndian>(values.len() as i32)?;
+            buffer.extend(values.iter().map(|value| *value as u8));
+        }
+        Tag::String(value) => write_nbt_string(buffer, value)?,
+        Tag::List(elements) => {
+            let element_id: u8 = elements.first().map_or(TAG_END, Tag::id);
+            if elements.iter().any(|element| element.id() != element_id) {
+                return Err(Error::new(ErrorKind::InvalidInput, "NBT list elements must share one type"));
+            }
+            buffer.push(element_id);
+            buffer.write_i32::<BigEndian>(elements.len() as i32)?;
+            for element in elements {
+                write_payload(buffer, element)?;
+            }
+        }
+        Tag::Compound(entries) => {
+            for (name, entry) in entries {
+                write_named_tag(buffer, name, entry)?;
+            }
+            buffer.push(TAG_END);
+        }
+        Tag::IntArray(values) => {
+            buffer.write_i32::<BigEndian>(values.len() as i32)?;
+            for value in values {
+                buffer.write_i32::<BigEndian>(*value)?;
+            }
+        }
+        Tag::LongArray(values) => {
+            buffer.write_i32::<BigEndian>(values.len() as i32)?;
+            for value in values {
+                buffer.write_i64::<BigEndian>(*value)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+// NBT strings use Java's modified UTF-8: NUL is two bytes and characters
+// outside the BMP are written as two three-byte surrogates.
+fn read_nbt_string<R: Read>(stream: &mut R) -> io::Result<String> {
+    let length: usize = stream.read_u16::<BigEndian>()? as usize;
+    let mut bytes: Vec<u8> = vec![0; length];
+    stream.read_exact(&mut bytes)?;
+
+    let invalid = || Error::new(ErrorKind::InvalidData, "NBT string is not valid modified UTF-8");
+    let mut units: Vec<u16> = Vec::with_capacity(length);
+    let mut i: usize = 0;
+    while i < bytes.len() {
+        let first: u16 = bytes[i] as u16;
+        let continuation = |index: usize| -> io::Result<u16> {
+            match bytes.get(index) {
+                Some(byte) if byte & 0xC0 == 0x80 => Ok((byte & 0x3F) as u16),
+                _ => Err(invalid()),
+            }
+        };
+        if first < 0x80 {
+            units.push(first);
+            i += 1;
+        } else if first & 0xE0 == 0xC0 {
+            units.push(((first & 0x1F) << 6) | continuation(i + 1)?);
+            i += 2;
+        } else if first & 0xF0 == 0xE0 {
+            units.push(((first & 0x0F) << 12) | (continuation(i + 1)? << 6) | continuation(i + 2)?);
+            i += 3;
+        } else {
+            return Err(invalid());
+        }
+    }
+    String::from_utf16(&units).map_err(|_| invalid())
+}
+
+fn write_nbt_string(buffer: &mut Vec<u8>, value: &str) -> io::Result<()> {
+    let mut bytes: Vec<u8> = Vec::with_capacity(value.len());
+    for unit in value.encode_utf16() {
+        match unit {
+            0x0001..=0x007F => bytes.push(unit as u8),
+            0x0000..=0x07FF => {
+                bytes.push(0xC0 | (unit >> 6) as u8);
+                bytes.push(0x80 | (unit & 0x3F) as u8);
+            }
+            _ => {
+                bytes.push(0xE0 | (unit >> 12) as u8);
+                bytes.push(0x80 | ((unit >> 6) & 0x3F) as u8);
+                bytes.push(0x80 | (unit & 0x3F) as u8);
+            }
+        }
+    }
+    if bytes.len() > u16::MAX as usize {
+        return Err(Error::new(ErrorKind::InvalidInput, "NBT string is too long"));
+    }
+    buffer.write_u16::<BigEndian>(bytes.len() as u16)?;
+    buffer.extend_from_slice(&bytes);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+    use std::io::Cursor;
+
+    fn round_trip(tag: &Tag) -> Tag {
+        let mut buffer: Vec<u8> = Vec::new();
+        write_named_tag(&mut buffer, "root", tag).unwrap();
+        let (name, read) = read_named_tag(&mut Cursor::new(&buffer)).unwrap();
+        assert_eq!(name, "root");
+        read
+    }
+
+    #[test]
+    fn hello_world() {
+        // "hello_world.nbt" from the original NBT specification.
+        let bytes: &[u8] = &[
+            0x0a, 0x00, 0x0b, b'h', b'e', b'l', b'l', b'o', b' ', b'w', b'o', b'r', b'l', b'd',
+            0x08, 0x00, 0x04, b'n', b'a', b'm', b'e', 0x00, 0x09, b'B', b'a', b'n', b'a', b'n', b'r', b'a', b'm', b'a',
+            0x00,
+        ];
+        let (name, tag) = read_named_tag(&mut Cursor::new(bytes)).unwrap();
+        assert_eq!(name, "hello world");
+        assert_eq!(tag.get("name"), Some(&Tag::String("Bananrama".to_string())));
+
+        let mut buffer: Vec<u8> = Vec::new();
+        write_named_tag(&mut buffer, &name, &tag).unwrap();
+        assert_eq!(buffer, bytes);
+    }
+
+    #[test]
+    fn every_tag_type_round_trips() {
+        let tag = Tag::Compound(vec![
+            ("byte".to_string(), Tag::Byte(-1)),
+            ("short".to_string(), Tag::Short(300)),
+            ("int".to_string(), Tag::Int(-70000)),
+            ("long".to_string(), Tag::Long(i64::MIN)),
+            ("float".to_string(), Tag::Float(0.5)),
+            ("double".to_string(), Tag::Double(-2.25)),
+            ("bytes".to_string(), Tag::ByteArray(vec![1, -2, 3])),
+            ("string".to_string(), Tag::String("\0 ünï 🐙".to_string())),
+            ("list".to_string(), Tag::List(vec![Tag::Int(1), Tag::Int(2)])),
+            ("empty".to_string(), Tag::List(Vec::new())),
+            ("nested".to_string(), Tag::Compound(vec![("x".to_string(), Tag::Byte(1))])),
+            ("ints".to_string(), Tag::IntArray(vec![i32::MAX, 0])),
+            ("longs".to_string(), Tag::LongArray(vec![i64::MAX])),
+        ]);
+        assert_eq!(round_trip(&tag), tag);
+    }
+
+    #[test]
+    fn modified_utf8_encoding() {
+        let mut buffer: Vec<u8> = Vec::new();
+        write_nbt_string(&mut buffer, "\0🐙").unwrap();
+        assert_eq!(buffer, [0x00, 0x08, 0xC0, 0x80, 0xED, 0xA0, 0xBD, 0xED, 0xB0, 0x99]);
+    }
+
+    #[test]
+    fn empty_root() {
+        assert_eq!(read_named_tag(&mut Cursor::new([0x00])).unwrap(), (String::new(), Tag::End));
+    }
+
+    #[test]
+    fn malformed_nbt_is_an_error() {
+        assert!(read_named_tag(&mut Cursor::new([0x0d, 0x00, 0x00])).is_err());
+        assert!(read_named_tag(&mut Cursor::new([0x07, 0x00, 0x00, 0x7f, 0xff, 0xff, 0xff])).is_err());
+        assert!(read_named_tag(&mut Cursor::new([0x08, 0x00, 0x00, 0x00, 0x02, 0xC0, 0x00])).is_err());
+        let mut deep: Vec<u8> = vec![0x09, 0x00, 0x00];
+        for _ in 0..600 {
+            deep.extend_from_slice(&[0x09, 0x00, 0x00, 0x00, 0x01]);
+        }
+        assert!(read_named_tag(&mut Cursor::new(deep)).is_err());
+    }
+
+    proptest! {
+        #[test]
+        fn string_round_trip(value: String) {
+            prop_assume!(value.len() < 20000);
+            prop_assert_eq!(round_trip(&Tag::String(value.clone())), Tag::String(value));
+        }
+
+        #[test]
+        fn read_never_panics(bytes: Vec<u8>) {
+            let _ = read_named_tag(&mut Cursor::new(bytes));
+        }
+    }
+}
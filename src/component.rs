@@ -0,0 +1,859 @@
+use serde::{Deserialize, Deserializer};
+use serde_json::{Map, Value};
+use crate::lang;
+
+/// Name, legacy `§` code and RGB value of the 16 named chat colors.
+pub const NAMED_COLORS: [(&str, char, [u8; 3]); 16] = [
+    ("black", '0', [0, 0, 0]),
+    ("dark_blue", '1', [0, 0, 170]),
+    ("dark_green", '2', [0, 170, 0]),
+    ("dark_aqua", '3', [0, 170, 170]),
+    ("dark_red", '4', [170, 0, 0]),
+    ("dark_purple", '5', [170, 0, 170]),
+    ("gold", '6', [255, 170, 0]),
+    ("gray", '7', [170, 170, 170]),
+    ("dark_gray", '8', [85, 85, 85]),
+    ("blue", '9', [85, 85, 255]),
+    ("green", 'a', [85, 255, 85]),
+    ("aqua", 'b', [85, 255, 255]),
+    ("red", 'c', [255, 85, 85]),
+    ("light_purple", 'd', [255, 85, 255]),
+    ("yellow", 'e', [255, 255, 85]),
+    ("white", 'f', [255, 255, 255]),
+];
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Color(pub [u8; 3]);
+
+impl Color {
+    pub const WHITE: Color = Color([255, 255, 255]);
+
+    /// Parses a named color or a `#RRGGBB` hex color.
+    pub fn parse(color: &str) -> Option<Color> {
+        if let Some(hex) = color.strip_prefix('#') {
+            return hex_to_rgb(hex).ok().map(Color);
+        }
+        NAMED_COLORS
+            .iter()
+            .find(|(name, _, _)| *name == color)
+            .map(|(_, _, rgb)| Color(*rgb))
+    }
+
+    pub fn from_legacy_code(code: char) -> Option<Color> {
+        NAMED_COLORS
+            .iter()
+            .find(|(_, legacy_code, _)| *legacy_code == code.to_ascii_lowercase())
+            .map(|(_, _, rgb)| Color(*rgb))
+    }
+}
+
+fn hex_to_rgb(hex: &str) -> Result<[u8; 3], &str> {
+    if hex.len() != 6 || !hex.is_ascii() {
+        return Err("Invalid hexadecimal color code");
+    }
+
+    let r = u8::from_str_radix(&hex[0..2], 16).map_err(|_| "Invalid hexadecimal color code")?;
+    let g = u8::from_str_radix(&hex[2..4], 16).map_err(|_| "Invalid hexadecimal color code")?;
+    let b = u8::from_str_radix(&hex[4..6], 16).map_err(|_| "Invalid hexadecimal color code")?;
+
+    Ok([r, g, b])
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ClickAction {
+    OpenUrl,
+    OpenFile,
+    RunCommand,
+    SuggestCommand,
+    ChangePage,
+    CopyToClipboard,
+}
+
+impl ClickAction {
+    fn parse(action: &str) -> Option<ClickAction> {
+        match action {
+            "open_url" => Some(ClickAction::OpenUrl),
+            "open_file" => Some(ClickAction::OpenFile),
+            "run_command" => Some(ClickAction::RunCommand),
+            "suggest_command" => Some(ClickAction::SuggestCommand),
+            "change_page" => Some(ClickAction::ChangePage),
+            "copy_to_clipboard" => Some(ClickAction::CopyToClipboard),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct ClickEvent {
+    pub action: ClickAction,
+    pub value: String,
+}
+
+impl ClickEvent {
+    fn from_json(json: &Value) -> Option<ClickEvent> {
+        let action = ClickAction::parse(json.get("action")?.as_str()?)?;
+        // change_page takes a number, everything else a string.
+        let value = match json.get("value")? {
+            Value::String(value) => value.clone(),
+            value => value.to_string(),
+        };
+        Some(ClickEvent { action, value })
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum HoverEvent {
+    ShowText(Box<Component>),
+    ShowItem {
+        id: String,
+        count: i64,
+    },
+    ShowEntity {
+        name: Option<Box<Component>>,
+        entity_type: String,
+        id: String,
+    },
+}
+
+impl HoverEvent {
+    /// Reads both the `contents` form and the `value` form servers sent
+    /// before 1.16. An old style item or entity is kept as its SNBT text.
+    fn from_json(json: &Value) -> Option<HoverEvent> {
+        let action = json.get("action")?.as_str()?;
+        let string = |json: &Value, name: &str| json.get(name).and_then(Value::as_str).unwrap_or_default().to_string();
+        if let Some(contents) = json.get("contents") {
+            return match action {
+                "show_text" => Some(HoverEvent::ShowText(Box::new(Component::from_json(contents).ok()?))),
+                "show_item" => match contents {
+                    Value::String(id) => Some(HoverEvent::ShowItem { id: id.clone(), count: 1 }),
+                    contents => Some(HoverEvent::ShowItem {
+                        id: string(contents, "id"),
+                        count: contents.get("count").and_then(Value::as_i64).unwrap_or(1),
+                    }),
+                },
+                "show_entity" => Some(HoverEvent::ShowEntity {
+                    name: contents.get("name").and_then(|name| Component::from_json(name).ok()).map(Box::new),
+                    entity_type: string(contents, "type"),
+                    id: match contents.get("id") {
+                        Some(Value::String(id)) => id.clone(),
+                        Some(id) => id.to_string(),
+                        None => String::new(),
+                    },
+                }),
+                _ => None,
+            };
+        }
+
+        let value = Component::from_json(json.get("value")?).ok()?;
+        match action {
+            "show_text" => Some(HoverEvent::ShowText(Box::new(value))),
+            "show_item" => Some(HoverEvent::ShowItem { id: value.to_plain_text(), count: 1 }),
+            "show_entity" => Some(HoverEvent::ShowEntity {
+                name: None,
+                entity_type: String::new(),
+                id: value.to_plain_text(),
+            }),
+            _ => None,
+        }
+    }
+
+    /// The text the vanilla client would show in the tooltip.
+    pub fn to_component(&self) -> Component {
+        match self {
+            HoverEvent::ShowText(text) => (**text).clone(),
+            HoverEvent::ShowItem { id, count } if *count > 1 => Component::text(&format!("{} x{}", id, count)),
+            HoverEvent::ShowItem { id, .. } => Component::text(id),
+            HoverEvent::ShowEntity { name, entity_type, id } => {
+                let mut tooltip = match name {
+                    Some(name) => (**name).clone(),
+                    None => Component::default(),
+                };
+                for line in [entity_type, id] {
+                    if !line.is_empty() {
+                        if tooltip != Component::default() {
+                            tooltip.extra.push(Component::text("\n"));
+                        }
+                        tooltip.extra.push(Component::text(line));
+                    }
+                }
+                tooltip
+            }
+        }
+    }
+}
+
+/// Formatting of a component. Unset fields are inherited from the parent.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Style {
+    pub color: Option<Color>,
+    pub bold: Option<bool>,
+    pub italic: Option<bool>,
+    pub underlined: Option<bool>,
+    pub strikethrough: Option<bool>,
+    pub obfuscated: Option<bool>,
+    pub insertion: Option<String>,
+    pub font: Option<String>,
+    pub click_event: Option<ClickEvent>,
+    pub hover_event: Option<HoverEvent>,
+}
+
+impl Style {
+    /// Fills the fields this style leaves unset from `parent`.
+    pub fn inherit(&self, parent: &Style) -> Style {
+        Style {
+            color: self.color.or(parent.color),
+            bold: self.bold.or(parent.bold),
+            italic: self.italic.or(parent.italic),
+            underlined: self.underlined.or(parent.underlined),
+            strikethrough: self.strikethrough.or(parent.strikethrough),
+            obfuscated: self.obfuscated.or(parent.obfuscated),
+            insertion: self.insertion.clone().or_else(|| parent.insertion.clone()),
+            font: self.font.clone().or_else(|| parent.font.clone()),
+            click_event: self.click_event.clone().or_else(|| parent.click_event.clone()),
+            hover_event: self.hover_event.clone().or_else(|| parent.hover_event.clone()),
+        }
+    }
+
+    fn from_object(object: &Map<String, Value>) -> Style {
+        let flag = |name: &str| object.get(name).and_then(Value::as_bool);
+        let string = |name: &str| object.get(name).and_then(Value::as_str).map(str::to_string);
+        Style {
+            color: object.get("color").and_then(Value::as_str).and_then(Color::parse),
+            bold: flag("bold"),
+            italic: flag("italic"),
+            underlined: flag("underlined"),
+            strikethrough: flag("strikethrough"),
+            obfuscated: flag("obfuscated"),
+            insertion: string("insertion"),
+            font: string("font"),
+            click_event: object.get("clickEvent").and_then(ClickEvent::from_json),
+            hover_event: object.get("hoverEvent").and_then(HoverEvent::from_json),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum NbtSource {
+    Block(String),
+    Entity(String),
+    Storage(String),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Content {
+    Text(String),
+    Translate {
+        key: String,
+        with: Vec<Component>,
+    },
+    Score {
+        name: String,
+        objective: String,
+        value: Option<String>,
+    },
+    Selector {
+        pattern: String,
+        separator: Option<Box<Component>>,
+    },
+    Keybind(String),
+    Nbt {
+        path: String,
+        interpret: bool,
+        separator: Option<Box<Component>>,
+        source: Option<NbtSource>,
+    },
+}
+
+impl Default for Content {
+    fn default() -> Self {
+        Content::Text(String::new())
+    }
+}
+
+/// A chat component: its own content, its style and the components appended
+/// after it, which inherit that style.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Component {
+    pub content: Content,
+    pub style: Style,
+    pub extra: Vec<Component>,
+}
+
+/// A run of text with its fully inherited style, ready to be rendered.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Span {
+    pub text: String,
+    pub style: Style,
+}
+
+impl Component {
+    pub fn text(text: &str) -> Component {
+        Component {
+            content: Content::Text(text.to_string()),
+            ..Component::default()
+        }
+    }
+
+    /// Reads a component from any of its JSON forms. A string is plain text,
+    /// and in an array the first element is the parent of all the others.
+    pub fn from_json(json: &Value) -> Result<Component, String> {
+        match json {
+            Value::String(text) => Ok(Component::text(text)),
+            Value::Number(_) | Value::Bool(_) => Ok(Component::text(&json.to_string())),
+            Value::Array(items) => {
+                let mut items = items.iter();
+                let mut component = match items.next() {
+                    Some(first) => Component::from_json(first)?,
+                    None => return Err("Empty chat component array".to_string()),
+                };
+                for item in items {
+                    component.extra.push(Component::from_json(item)?);
+                }
+                Ok(component)
+            }
+            Value::Object(object) => Component::from_object(object),
+            Value::Null => Err("Chat component is null".to_string()),
+        }
+    }
+
+    fn from_object(object: &Map<String, Value>) -> Result<Component, String> {
+        let optional_component = |name: &str| -> Result<Option<Box<Component>>, String> {
+            match object.get(name) {
+                Some(json) => Ok(Some(Box::new(Component::from_json(json)?))),
+                None => Ok(None),
+            }
+        };
+        let string = |json: Option<&Value>| json.and_then(Value::as_str).unwrap_or_default().to_string();
+
+        let content = if let Some(text) = object.get("text") {
+            Content::Text(match text {
+                Value::String(text) => text.clone(),
+                text => text.to_string(),
+            })
+        } else if let Some(key) = object.get("translate").and_then(Value::as_str) {
+            Content::Translate {
+                key: key.to_string(),
+                with: components_from_json(object.get("with"))?,
+            }
+        } else if let Some(score) = object.get("score").and_then(Value::as_object) {
+            Content::Score {
+                name: string(score.get("name")),
+                objective: string(score.get("objective")),
+                value: score.get("value").and_then(Value::as_str).map(str::to_string),
+            }
+        } else if let Some(pattern) = object.get("selector").and_then(Value::as_str) {
+            Content::Selector {
+                pattern: pattern.to_string(),
+                separator: optional_component("separator")?,
+            }
+        } else if let Some(keybind) = object.get("keybind").and_then(Value::as_str) {
+            Content::Keybind(keybind.to_string())
+        } else if let Some(path) = object.get("nbt").and_then(Value::as_str) {
+            let source = |name: &str| object.get(name).and_then(Value::as_str).map(str::to_string);
+            Content::Nbt {
+                path: path.to_string(),
+                interpret: object.get("interpret").and_then(Value::as_bool).unwrap_or(false),
+                separator: optional_component("separator")?,
+                source: source("block").map(NbtSource::Block)
+                    .or_else(|| source("entity").map(NbtSource::Entity))
+                    .or_else(|| source("storage").map(NbtSource::Storage)),
+            }
+        } else {
+            Content::default()
+        };
+
+        Ok(Component {
+            content,
+            style: Style::from_object(object),
+            extra: components_from_json(object.get("extra"))?,
+        })
+    }
+
+    /// Flattens the tree into styled runs of text, resolving translations and
+    /// style inheritance on the way.
+    ///
+    /// Arguments can hold translations that reuse their own arguments, so a
+    /// small message can expand without end. One that goes over the limits
+    /// is shown with its translation keys instead.
+    pub fn spans(&self) -> Vec<Span> {
+        let mut spans = Vec::<Span>::new();
+        let mut budget = Budget::new(MAX_TRANSLATE_DEPTH);
+        self.collect_spans(&Style::default(), &mut spans, &mut budget, 0);
+        if budget.exceeded {
+            spans.clear();
+            self.collect_spans(&Style::default(), &mut spans, &mut Budget::new(0), 0);
+        }
+        spans
+    }
+
+    fn collect_spans(&self, inherited_style: &Style, spans: &mut Vec<Span>, budget: &mut Budget, depth: usize) {
+        if budget.components == 0 || budget.chars == 0 {
+            budget.exceeded = true;
+            return;
+        }
+        budget.components -= 1;
+        let first_new = spans.len();
+        let style = self.style.inherit(inherited_style);
+        match &self.content {
+            Content::Text(text) => push_legacy_spans(spans, text, &style),
+            Content::Translate { key, .. } if depth >= budget.max_depth => {
+                if budget.max_depth > 0 {
+                    budget.exceeded = true;
+                }
+                push_span(spans, key, &style);
+            }
+            Content::Translate { key, with } => {
+                for part in translate_parts(key, with) {
+                    match part {
+                        TranslatedPart::Text(text) => push_span(spans, &text, &style),
+                        TranslatedPart::Argument(argument) => argument.collect_spans(&style, spans, budget, depth + 1),
+                    }
+                }
+            }
+            Content::Score { name, objective, value } => match value {
+                Some(value) => push_span(spans, value, &style),
+                None => push_span(spans, &format!("{}/{}", name, objective), &style),
+            },
+            // The server resolves selectors into `extra`, the pattern only
+            // shows if it did not.
+            Content::Selector { pattern, .. } if self.extra.is_empty() => push_span(spans, pattern, &style),
+            Content::Selector { .. } => {}
+            Content::Keybind(keybind) => push_span(spans, &lang::key_name(keybind), &style),
+            Content::Nbt { path, .. } => push_span(spans, &format!("[{}]", path), &style),
+        }
+        let chars: usize = spans[first_new..].iter().map(|span| span.text.len()).sum();
+        budget.chars = budget.chars.saturating_sub(chars);
+        for child in &self.extra {
+            child.collect_spans(&style, spans, budget, depth);
+        }
+    }
+
+    /// Renders the component as text without any formatting.
+    pub fn to_plain_text(&self) -> String {
+        self.spans().into_iter().map(|span| span.text).collect()
+    }
+}
+
+impl<'de> Deserialize<'de> for Component {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let json = Value::deserialize(deserializer)?;
+        Component::from_json(&json).map_err(serde::de::Error::custom)
+    }
+}
+
+fn components_from_json(json: Option<&Value>) -> Result<Vec<Component>, String> {
+    match json {
+        None | Some(Value::Null) => Ok(Vec::new()),
+        Some(Value::Array(items)) => items.iter().map(Component::from_json).collect(),
+        Some(json) => Ok(vec![Component::from_json(json)?]),
+    }
+}
+
+fn push_span(spans: &mut Vec<Span>, text: &str, style: &Style) {
+    if !text.is_empty() {
+        spans.push(Span {
+            text: text.to_string(),
+            style: style.clone(),
+        });
+    }
+}
+
+/// Reads the six `§R§R§G§G§B§B` pairs that follow `§x` in the hex color
+/// format of Bukkit plugins.
+fn legacy_hex_color(chars: &[char]) -> Option<Color> {
+    if chars.len() < 12 {
+        return None;
+    }
+    let mut hex = String::new();
+    for pair in chars[..12].chunks(2) {
+        if pair[0] != '§' || !pair[1].is_ascii_hexdigit() {
+            return None;
+        }
+        hex.push(pair[1]);
+    }
+    hex_to_rgb(&hex).ok().map(Color)
+}
+
+/// Splits text on legacy `§` formatting codes the way the vanilla client
+/// does: a color clears the formatting before it, `§r` goes back to the
+/// style of the component and unknown codes are dropped.
+fn push_legacy_spans(spans: &mut Vec<Span>, text: &str, parent: &Style) {
+    let chars: Vec<char> = text.chars().collect();
+    let mut style = parent.clone();
+    let mut run = String::new();
+    let mut i = 0;
+
+    while i < chars.len() {
+        if chars[i] != '§' {
+            run.push(chars[i]);
+            i += 1;
+            continue;
+        }
+        let Some(&code) = chars.get(i + 1) else {
+            break;
+        };
+        i += 2;
+
+        let color = match code.to_ascii_lowercase() {
+            'x' => legacy_hex_color(&chars[i..]).inspect(|_| i += 12),
+            code => Color::from_legacy_code(code),
+        };
+        let next_style = if let Some(color) = color {
+            Style {
+                color: Some(color),
+                bold: Some(false),
+                italic: Some(false),
+                underlined: Some(false),
+                strikethrough: Some(false),
+                obfuscated: Some(false),
+                ..style.clone()
+            }
+        } else {
+            let mut next_style = style.clone();
+            match code.to_ascii_lowercase() {
+                'k' => next_style.obfuscated = Some(true),
+                'l' => next_style.bold = Some(true),
+                'm' => next_style.strikethrough = Some(true),
+                'n' => next_style.underlined = Some(true),
+                'o' => next_style.italic = Some(true),
+                'r' => next_style = parent.clone(),
+                _ => {}
+            }
+            next_style
+        };
+
+        if next_style != style {
+            push_span(spans, &std::mem::take(&mut run), &style);
+            style = next_style;
+        }
+    }
+    push_span(spans, &run, &style);
+}
+
+/// How deep translations may nest in the arguments of other translations.
+const MAX_TRANSLATE_DEPTH: usize = 8;
+/// How many components and how much text one message may expand to.
+const MAX_COMPONENTS: usize = 10_000;
+const MAX_CHARS: usize = 65_536;
+
+/// What is left of the limits while a component is flattened.
+struct Budget {
+    max_depth: usize,
+    components: usize,
+    chars: usize,
+    exceeded: bool,
+}
+
+impl Budget {
+    fn new(max_depth: usize) -> Budget {
+        Budget {
+            max_depth,
+            components: MAX_COMPONENTS,
+            chars: MAX_CHARS,
+            exceeded: false,
+        }
+    }
+}
+
+enum Placeholder {
+    Literal(String),
+    Argument(usize),
+}
+
+/// Splits a translation into literal text and argument slots the way the
+/// vanilla client does: `%s` takes the next argument, `%2$s` a numbered one
+/// and `%%` is a percent sign. Returns None if the format is malformed.
+fn split_placeholders(format: &str) -> Option<Vec<Placeholder>> {
+    let mut parts = Vec::<Placeholder>::new();
+    let mut literal = String::new();
+    let mut next_argument: usize = 0;
+    let mut chars = format.chars().peekable();
+
+    while let Some(ch) = chars.next() {
+        if ch != '%' {
+            literal.push(ch);
+            continue;
+        }
+
+        let mut digits = String::new();
+        while let Some(digit) = chars.next_if(|c| c.is_ascii_digit()) {
+            digits.push(digit);
+        }
+        if !digits.is_empty() && chars.next() != Some('$') {
+            return None;
+        }
+
+        match chars.next() {
+            Some('%') if digits.is_empty() => literal.push('%'),
+            Some('s') => {
+                let index = if digits.is_empty() {
+                    next_argument += 1;
+                    next_argument - 1
+                } else {
+                    digits.parse::<usize>().ok()?.checked_sub(1)?
+                };
+                if !literal.is_empty() {
+                    parts.push(Placeholder::Literal(std::mem::take(&mut literal)));
+                }
+                parts.push(Placeholder::Argument(index));
+            }
+            _ => return None,
+        }
+    }
+
+    if !literal.is_empty() {
+        parts.push(Placeholder::Literal(literal));
+    }
+    Some(parts)
+}
+
+/// Builds the parts of a translated message. Literal parts of the translation
+/// become plain text, each argument stays the component the server sent, so
+/// it keeps its own style and can hold a translation of its own. All parts
+/// inherit the style of the translate component.
+pub fn translate(key: &str, with: &[Component]) -> Vec<Component> {
+    translate_parts(key, with)
+        .into_iter()
+        .map(|part| match part {
+            TranslatedPart::Text(text) => Component::text(&text),
+            TranslatedPart::Argument(argument) => argument.clone(),
+        })
+        .collect()
+}
+
+enum TranslatedPart<'a> {
+    Text(String),
+    Argument(&'a Component),
+}
+
+/// Like `translate`, borrowing the arguments instead of copying them.
+fn translate_parts<'a>(key: &str, with: &'a [Component]) -> Vec<TranslatedPart<'a>> {
+    // Unknown keys are shown as is, followed by their arguments, unless they
+    // name an item, block or the like.
+    let format = match lang::get(key) {
+        Some(format) => format,
+        None if with.is_empty() => return vec![TranslatedPart::Text(lang::registry_name(key).unwrap_or_else(|| key.to_string()))],
+        None => {
+            let mut parts = vec![TranslatedPart::Text(key.to_string()), TranslatedPart::Text(" [".to_string())];
+            for (i, argument) in with.iter().enumerate() {
+                if i > 0 {
+                    parts.push(TranslatedPart::Text(", ".to_string()));
+                }
+                parts.push(TranslatedPart::Argument(argument));
+            }
+            parts.push(TranslatedPart::Text("]".to_string()));
+            return parts;
+        }
+    };
+
+    let placeholders = split_placeholders(format).filter(|placeholders| {
+        placeholders.iter().all(|placeholder| match placeholder {
+            Placeholder::Argument(index) => *index < with.len(),
+            Placeholder::Literal(_) => true,
+        })
+    });
+
+    // Like vanilla, a translation that does not fit its arguments is shown
+    // without substitutions.
+    match placeholders {
+        Some(placeholders) => placeholders
+            .into_iter()
+            .map(|placeholder| match placeholder {
+                Placeholder::Literal(text) => TranslatedPart::Text(text),
+                Placeholder::Argument(index) => TranslatedPart::Argument(&with[index]),
+            })
+            .collect(),
+        None => vec![TranslatedPart::Text(format.to_string())],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn component(json: Value) -> Component {
+        serde_json::from_value(json).unwrap()
+    }
+
+    #[test]
+    fn string_array_and_object_forms() {
+        assert_eq!(component(json!("hi")), Component::text("hi"));
+        assert_eq!(component(json!({"text": "hi"})), Component::text("hi"));
+
+        let array = component(json!([{"text": "a", "color": "red"}, "b", {"text": "c", "bold": true}]));
+        assert_eq!(array.style.color, Color::parse("red"));
+        assert_eq!(array.extra.len(), 2);
+        let spans = array.spans();
+        assert_eq!(spans[1].style.color, Color::parse("red"));
+        assert_eq!(spans[2].style.bold, Some(true));
+
+        assert!(serde_json::from_value::<Component>(json!([])).is_err());
+        assert!(serde_json::from_value::<Component>(json!(null)).is_err());
+    }
+
+    #[test]
+    fn every_content_type_is_read() {
+        let score = component(json!({"score": {"name": "Steve", "objective": "kills", "value": "3"}}));
+        assert_eq!(score.content, Content::Score {
+            name: "Steve".to_string(),
+            objective: "kills".to_string(),
+            value: Some("3".to_string()),
+        });
+        let selector = component(json!({"selector": "@a", "separator": ", "}));
+        assert_eq!(selector.content, Content::Selector {
+            pattern: "@a".to_string(),
+            separator: Some(Box::new(Component::text(", "))),
+        });
+        assert_eq!(component(json!({"keybind": "key.jump"})).content, Content::Keybind("key.jump".to_string()));
+        let nbt = component(json!({"nbt": "Health", "entity": "@s", "interpret": true}));
+        assert_eq!(nbt.content, Content::Nbt {
+            path: "Health".to_string(),
+            interpret: true,
+            separator: None,
+            source: Some(NbtSource::Entity("@s".to_string())),
+        });
+    }
+
+    #[test]
+    fn every_content_type_is_rendered() {
+        let text = |json: Value| component(json).to_plain_text();
+        assert_eq!(text(json!({"score": {"name": "Steve", "objective": "kills", "value": "3"}})), "3");
+        assert_eq!(text(json!({"score": {"name": "Steve", "objective": "kills"}})), "Steve/kills");
+        assert_eq!(text(json!({"selector": "@a"})), "@a");
+        assert_eq!(text(json!({"selector": "@a", "extra": ["Alex", ", ", "Steve"]})), "Alex, Steve");
+        assert_eq!(text(json!({"keybind": "key.sneak", "color": "gold"})), "Left Shift");
+        assert_eq!(text(json!({"nbt": "Inventory[0]", "entity": "@s"})), "[Inventory[0]]");
+    }
+
+    #[test]
+    fn styles_are_inherited_and_overridden() {
+        let message = component(json!({
+            "text": "a", "color": "#102030", "italic": true,
+            "extra": [{"text": "b", "italic": false}, {"text": "c", "color": "gold", "insertion": "x"}],
+        }));
+        let spans = message.spans();
+        assert_eq!(spans.len(), 3);
+        assert_eq!(spans[0].style.color, Some(Color([0x10, 0x20, 0x30])));
+        assert_eq!(spans[1].style.color, Some(Color([0x10, 0x20, 0x30])));
+        assert_eq!(spans[1].style.italic, Some(false));
+        assert_eq!(spans[2].style.color, Some(Color([255, 170, 0])));
+        assert_eq!(spans[2].style.italic, Some(true));
+        assert_eq!(spans[2].style.insertion.as_deref(), Some("x"));
+    }
+
+    #[test]
+    fn nested_translations_are_limited() {
+        // Each level uses the one below three times: 3^30 copies unlimited.
+        let mut json = json!("x");
+        for _ in 0..30 {
+            json = json!({"translate": "translation.test.complex", "with": [json, "a", "b"]});
+        }
+        let text = component(json).to_plain_text();
+        assert_eq!(text, "translation.test.complex");
+
+        let mut json = json!("#");
+        for _ in 0..2 {
+            json = json!({"translate": "translation.test.complex", "with": [json, "a", "b"]});
+        }
+        assert_eq!(component(json).to_plain_text().matches('#').count(), 9);
+    }
+
+    #[test]
+    fn click_and_hover_events() {
+        let message = component(json!({
+            "text": "a",
+            "clickEvent": {"action": "change_page", "value": 2},
+            "hoverEvent": {"action": "show_text", "contents": {"text": "tip", "color": "red"}},
+            "extra": [{"text": "b", "hoverEvent": {"action": "show_text", "value": ["old"]}}],
+        }));
+        let spans = message.spans();
+        assert_eq!(spans[1].style.click_event, Some(ClickEvent { action: ClickAction::ChangePage, value: "2".to_string() }));
+        let tooltip = |span: &Span| span.style.hover_event.as_ref().unwrap().to_component().to_plain_text();
+        assert_eq!(tooltip(&spans[0]), "tip");
+        assert_eq!(tooltip(&spans[1]), "old");
+
+        let item = component(json!({"text": "", "hoverEvent": {"action": "show_item", "contents": {"id": "minecraft:stone", "count": 3}}}));
+        assert_eq!(item.style.hover_event.unwrap().to_component().to_plain_text(), "minecraft:stone x3");
+        let entity = component(json!({"text": "", "hoverEvent": {"action": "show_entity",
+            "contents": {"type": "minecraft:pig", "id": "0-0-0-0-1", "name": "Babe"}}}));
+        assert_eq!(entity.style.hover_event.unwrap().to_component().to_plain_text(), "Babe\nminecraft:pig\n0-0-0-0-1");
+
+        let unknown = component(json!({"text": "", "clickEvent": {"action": "launch", "value": "x"}, "hoverEvent": "x"}));
+        assert_eq!(unknown.style, Style::default());
+    }
+
+    #[test]
+    fn legacy_formatting_codes() {
+        let message = component(json!({"text": "a§lb§cc§x§1§2§a§B§c§Dd§k§re§zf§", "italic": true}));
+        let spans = message.spans();
+        let texts: Vec<&str> = spans.iter().map(|span| span.text.as_str()).collect();
+        assert_eq!(texts, ["a", "b", "c", "d", "ef"]);
+        assert_eq!(message.to_plain_text(), "abcdef");
+        assert_eq!(spans[1].style.bold, Some(true));
+        assert_eq!(spans[2].style.color, Color::parse("red"));
+        assert_eq!(spans[2].style.bold, Some(false));
+        assert_eq!(spans[2].style.italic, Some(false));
+        assert_eq!(spans[3].style.color, Some(Color([0x12, 0xab, 0xcd])));
+        assert_eq!(spans[4].style, message.style);
+
+        let bad_hex = component(json!("§x§1§2z§kq"));
+        assert_eq!(bad_hex.to_plain_text(), "zq");
+        assert_eq!(bad_hex.spans()[0].style.color, Color::parse("dark_green"));
+        assert_eq!(bad_hex.spans()[1].style.obfuscated, Some(true));
+    }
+
+    #[test]
+    fn malformed_styles_are_ignored() {
+        let message = component(json!({"text": "x", "color": "#ééé", "bold": "yes", "extra": {"text": "y"}}));
+        assert_eq!(message.style, Style::default());
+        assert_eq!(message.to_plain_text(), "xy");
+    }
+
+    #[test]
+    fn arguments_keep_their_style() {
+        let message = component(json!({
+            "translate": "multiplayer.player.joined", "color": "yellow",
+            "with": [{"text": "Steve", "color": "aqua"}],
+        }));
+        let spans = message.spans();
+        assert_eq!(message.to_plain_text(), "Steve joined the game");
+        assert_eq!(spans[0].style.color, Color::parse("aqua"));
+        assert_eq!(spans[1].style.color, Color::parse("yellow"));
+    }
+
+    #[test]
+    fn nested_translations() {
+        let message = component(json!({
+            "translate": "death.attack.mob",
+            "with": ["Alex", {"translate": "entity.minecraft.zombie", "bold": true}],
+        }));
+        assert_eq!(message.to_plain_text(), "Alex was slain by Zombie");
+        assert_eq!(message.spans()[2].style.bold, Some(true));
+    }
+
+    #[test]
+    fn numbered_and_escaped_placeholders() {
+        let arguments = [Component::text("a"), Component::text("b"), Component::text("c")];
+        let text = |parts: Vec<Component>| parts.iter().map(Component::to_plain_text).collect::<String>();
+        assert_eq!(text(translate("translation.test.complex", &arguments)), "Prefix, ab again b and a lastly c and also a again!");
+        assert_eq!(text(translate("translation.test.escape", &arguments)), "%s %a %%s %%b");
+        assert_eq!(component(json!({"translate": "chat.type.text", "with": ["Steve", 42]})).to_plain_text(), "<Steve> 42");
+    }
+
+    #[test]
+    fn malformed_translations_are_shown_raw() {
+        assert_eq!(component(json!({"translate": "translation.test.invalid", "with": ["a"]})).to_plain_text(), "hi %");
+        assert_eq!(component(json!({"translate": "multiplayer.player.joined"})).to_plain_text(), "%s joined the game");
+    }
+
+    #[test]
+    fn missing_key_shows_key_and_arguments() {
+        let message = component(json!({"translate": "custom.plugin.message", "with": ["a", {"text": "b", "bold": true}]}));
+        assert_eq!(message.to_plain_text(), "custom.plugin.message [a, b]");
+        assert_eq!(message.spans()[4].style.bold, Some(true));
+        assert_eq!(component(json!({"translate": "custom.plugin.message"})).to_plain_text(), "custom.plugin.message");
+        let give = component(json!({"translate": "commands.give.success.single", "with": [1, {"translate": "item.minecraft.golden_apple"}, "Steve"]}));
+        assert_eq!(give.to_plain_text(), "Gave 1 Golden Apple to Steve");
+    }
+}
@@ -0,0 +1,90 @@
+use std::fs::File;
+use std::io;
+use std::io::{Error, ErrorKind};
+use serde::Deserialize;
+
+/// Client settings read from a JSON file. Every field is optional, a missing
+/// file or field keeps the default.
+#[derive(Deserialize)]
+#[serde(default)]
+pub struct Config {
+    /// A vanilla locale file such as `ro_ro.json`, used over the bundled
+    /// table, which only holds the chat related part of en_us.
+    pub language_file: Option<String>,
+    /// Draws the vanilla text shadow as a dark background behind chat text.
+    pub background_shading: bool,
+    /// Milliseconds to wait between the parts of a message that was too long
+    /// to send at once, so the server's spam filter does not kick in.
+    pub split_delay_ms: u64,
+    /// A file every received chat message is appended to, for `/export`.
+    pub chat_log: Option<String>,
+    /// Message types not to show: `chat`, `system` or `action_bar`.
+    pub hidden_message_types: Vec<String>,
+    /// How a player joining is shown, with `{name}`, `{uuid}` and `{time}`.
+    /// Empty to show nothing.
+    pub join_format: String,
+    /// How a player leaving is shown, like `join_format` plus `{duration}`,
+    /// how long they were online.
+    pub leave_format: String,
+    /// Where `/seen` keeps when each player was last online.
+    pub history_file: String,
+    /// Milliseconds between anti-AFK moves, a small turn and a sneak, or
+    /// none to stand still.
+    pub anti_afk_interval_ms: Option<u64>,
+    /// Respawns right after dying instead of waiting for `/respawn`.
+    pub auto_respawn: bool,
+    /// The language the server is told the client uses, like `ro_ro`.
+    pub locale: String,
+    /// In chunks, some servers send entities and such only that far.
+    pub view_distance: u8,
+    pub chat_mode: ChatMode,
+    /// Whether the server may send colored chat.
+    pub chat_colors: bool,
+    /// The client name sent on the `minecraft:brand` channel.
+    pub brand: String,
+}
+
+/// Which messages the server should send, the chat setting of the game.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ChatMode {
+    Full,
+    CommandsOnly,
+    Hidden,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            language_file: None,
+            background_shading: false,
+            split_delay_ms: 1000,
+            chat_log: None,
+            hidden_message_types: Vec::new(),
+            join_format: "[{time}] {name} joined".to_string(),
+            leave_format: "[{time}] {name} left after {duration}".to_string(),
+            history_file: "history.json".to_string(),
+            anti_afk_interval_ms: None,
+            auto_respawn: true,
+            locale: "en_us".to_string(),
+            view_distance: 10,
+            chat_mode: ChatMode::Full,
+            chat_colors: true,
+            brand: "vanilla".to_string(),
+        }
+    }
+}
+
+impl Config {
+    pub fn load(path: &str) -> io::Result<Config> {
+        let config: Config = match File::open(path) {
+            Ok(file) => serde_json::from_reader(file)?,
+            Err(error) if error.kind() == ErrorKind::NotFound => Config::default(),
+            Err(error) => return Err(error),
+        };
+        if config.locale.len() > 16 {
+            return Err(Error::new(ErrorKind::InvalidData, "The locale can be at most 16 characters"));
+        }
+        Ok(config)
+    }
+}
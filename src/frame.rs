@@ -0,0 +1,162 @@
+use std::io;
+use std::io::Read;
+use std::io::{Error, ErrorKind};
+use yazi::{Adler32, Decoder, Format};
+use crate::codec::read_var_int_from_stream;
+
+// The length prefix of a frame is at most 3 bytes long.
+pub const MAX_PACKET_LENGTH: i32 = 2_097_151;
+// Largest uncompressed packet the protocol allows (2 MiB).
+pub const MAX_DATA_LENGTH: i32 = 2_097_152;
+
+pub fn read_frame<R: Read>(stream: &mut R) -> io::Result<Vec<u8>> {
+    let packet_length: i32 = read_var_int_from_stream(stream)?;
+    if !(0..=MAX_PACKET_LENGTH).contains(&packet_length) {
+        return Err(Error::new(ErrorKind::InvalidData, "Invalid packet length"));
+    }
+    let mut frame: Vec<u8> = vec![0; packet_length as usize];
+    stream.read_exact(&mut frame)?;
+    Ok(frame)
+}
+
+pub struct PacketDecoder {
+    decoder: Box<Decoder>,
+    threshold: i32,
+}
+
+impl PacketDecoder {
+    pub fn new(threshold: i32) -> PacketDecoder {
+        let mut decoder = Decoder::boxed();
+        decoder.set_format(Format::Zlib);
+        PacketDecoder {
+            decoder,
+            threshold,
+        }
+    }
+
+    pub fn decode(&mut self, data_length: i32, buffer: &[u8]) -> io::Result<Vec<u8>> {
+        if data_length > MAX_DATA_LENGTH {
+            return Err(Error::new(ErrorKind::InvalidData, "Decompressed packet is too big"));
+        }
+        if data_length < self.threshold || data_length <= 0 {
+            return Err(Error::new(ErrorKind::InvalidData, "Compressed packet is below the compression threshold"));
+        }
+
+        let mut decompressed_vec: Vec<u8> = vec![0; data_length as usize];
+        let mut decomp_stream = self.decoder.stream_into_buf(&mut decompressed_vec);
+        decomp_stream.write(buffer).map_err(invalid_compressed_data)?;
+        let (size, chekcsum) = decomp_stream.finish().map_err(invalid_compressed_data)?;
+
+        if size != data_length as u64 {
+            return Err(Error::new(ErrorKind::InvalidData, "Decompressed packet does not match its data length"));
+        }
+        if chekcsum != Some(Adler32::from_buf(&decompressed_vec).finish()) {
+            return Err(Error::new(ErrorKind::InvalidData, "Compressed packet checksum mismatch"));
+        }
+        Ok(decompressed_vec)
+    }
+}
+
+/// Packets that only change what is shown are skipped when malformed, like
+/// the odd ones NPC and scoreboard plugins send, instead of ending the
+/// connection. Other errors, such as failing to write to the server, still do.
+pub fn skip_malformed<T>(packet: &str, result: io::Result<T>) -> io::Result<Option<T>> {
+    match result {
+        Ok(value) => Ok(Some(value)),
+        Err(error) if matches!(error.kind(), ErrorKind::InvalidData | ErrorKind::UnexpectedEof) => {
+            println!("Skipped a malformed {} packet: {}", packet, error);
+            Ok(None)
+        }
+        Err(error) => Err(error),
+    }
+}
+
+fn invalid_compressed_data(error: yazi::Error) -> Error {
+    match error {
+        yazi::Error::Overflow => Error::new(ErrorKind::InvalidData, "Decompressed packet does not match its data length"),
+        yazi::Error::Io(error) => error,
+        _ => Error::new(ErrorKind::InvalidData, "Invalid compressed packet"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::codec::write_var_int;
+    use crate::players::Players;
+    use crate::teams::teams;
+    use proptest::prelude::*;
+    use std::io::Cursor;
+    use std::sync::{Arc, Mutex};
+    use yazi::{compress, CompressionLevel};
+
+    fn compressed(data: &[u8]) -> Vec<u8> {
+        compress(data, Format::Zlib, CompressionLevel::Default).unwrap()
+    }
+
+    #[test]
+    fn decoder_is_reusable() {
+        let mut decoder = PacketDecoder::new(4);
+        let first: Vec<u8> = (0..200).collect();
+        let second: Vec<u8> = b"hello hello hello".to_vec();
+        assert_eq!(decoder.decode(200, &compressed(&first)).unwrap(), first);
+        assert_eq!(decoder.decode(17, &compressed(&second)).unwrap(), second);
+    }
+
+    #[test]
+    fn decoder_rejects_bad_lengths() {
+        let data: Vec<u8> = vec![7; 300];
+        let mut decoder = PacketDecoder::new(256);
+        assert!(decoder.decode(299, &compressed(&data)).is_err());
+        assert!(decoder.decode(301, &compressed(&data)).is_err());
+        assert!(decoder.decode(MAX_DATA_LENGTH + 1, &compressed(&data)).is_err());
+        assert!(decoder.decode(100, &compressed(&data[..100])).is_err());
+        assert_eq!(decoder.decode(300, &compressed(&data)).unwrap(), data);
+    }
+
+    #[test]
+    fn malformed_display_packets_are_skipped() {
+        let players = Arc::new(Mutex::new(Players::new()));
+        // A team name whose length prefix never ends.
+        let packet: Vec<u8> = vec![0xff, 0xff, 0xff, 0xff, 0xff, 0x01];
+        assert!(matches!(skip_malformed("teams", teams(packet, Arc::clone(&players))), Ok(None)));
+        assert!(matches!(skip_malformed("teams", teams(vec![0x04, b'r'], players)), Ok(None)));
+        let closed: io::Result<()> = Err(Error::new(ErrorKind::BrokenPipe, "Connection closed"));
+        assert!(skip_malformed("teams", closed).is_err());
+    }
+
+    #[test]
+    fn frame_length_is_checked() {
+        let mut buffer: Vec<u8> = Vec::new();
+        write_var_int(&mut buffer, -1).unwrap();
+        assert!(read_frame(&mut Cursor::new(&buffer)).is_err());
+        assert!(read_frame(&mut Cursor::new([0x05, 0x01])).is_err());
+    }
+
+    proptest! {
+        #[test]
+        fn frame_round_trip(frames: Vec<Vec<u8>>) {
+            let mut buffer: Vec<u8> = Vec::new();
+            for frame in &frames {
+                write_var_int(&mut buffer, frame.len() as i32).unwrap();
+                buffer.extend_from_slice(frame);
+            }
+            let mut stream = Cursor::new(&buffer);
+            for frame in &frames {
+                prop_assert_eq!(&read_frame(&mut stream).unwrap(), frame);
+            }
+            prop_assert!(read_frame(&mut stream).is_err());
+        }
+
+        #[test]
+        fn decoder_round_trip(data in proptest::collection::vec(any::<u8>(), 1..4096)) {
+            let mut decoder = PacketDecoder::new(1);
+            prop_assert_eq!(decoder.decode(data.len() as i32, &compressed(&data)).unwrap(), data);
+        }
+
+        #[test]
+        fn decoder_never_panics(data_length in 0..MAX_DATA_LENGTH, bytes: Vec<u8>) {
+            let _ = PacketDecoder::new(0).decode(data_length, &bytes);
+        }
+    }
+}
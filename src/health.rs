@@ -0,0 +1,137 @@
+use std::io;
+use std::io::Cursor;
+use std::io::{Error, ErrorKind};
+use std::sync::{Arc, Mutex};
+use crate::codec::{read_float, read_int, read_string, read_var_int_from_stream, write_var_int};
+use crate::component::Component;
+
+/// The Client Status action that respawns a dead player.
+pub const RESPAWN: i32 = 0;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Health {
+    /// Half hearts, 20 when full.
+    pub health: f32,
+    pub food: i32,
+    pub saturation: f32,
+    /// On the death screen, until the server sends health again after a
+    /// respawn.
+    pub dead: bool,
+}
+
+impl Default for Health {
+    fn default() -> Health {
+        Health {
+            health: 20.0,
+            food: 20,
+            saturation: 5.0,
+            dead: false,
+        }
+    }
+}
+
+impl Health {
+    pub fn print(&self) {
+        if self.dead {
+            println!("You are dead, /respawn to play again");
+        } else {
+            println!("Health: {}/20  Food: {}/20  Saturation: {}", self.health, self.food, self.saturation);
+        }
+    }
+}
+
+/// Reads an Update Health packet. Returns whether it is what marked the
+/// player dead, as when joining while still on the death screen, where no
+/// Death Combat Event comes.
+pub fn update_health(buffer: Vec<u8>, health: Arc<Mutex<Health>>) -> io::Result<bool> {
+    let mut reader = Cursor::new(buffer);
+    let value = read_float(&mut reader)?;
+    let food = read_var_int_from_stream(&mut reader)?;
+    let saturation = read_float(&mut reader)?;
+    let mut health = health.lock().unwrap();
+    let was_dead = health.dead;
+    health.health = value;
+    health.food = food;
+    health.saturation = saturation;
+    health.dead = value <= 0.0;
+    Ok(health.dead && !was_dead)
+}
+
+/// Reads a Death Combat Event. Returns the death message if it is the
+/// player who died, the one whose entity id is given, and marks them dead.
+pub fn death_combat_event(buffer: Vec<u8>, health: Arc<Mutex<Health>>, entity_id: Option<i32>) -> io::Result<Option<Component>> {
+    let mut reader = Cursor::new(buffer);
+    let player_id = read_var_int_from_stream(&mut reader)?;
+    let _killer_id = read_int(&mut reader)?;
+    let json = read_string(&mut reader)?;
+    let message: Component =
+        serde_json::from_str(&json).map_err(|_| Error::new(ErrorKind::InvalidData, "Death message is not a valid chat component"))?;
+    if entity_id.is_some_and(|entity_id| entity_id != player_id) {
+        return Ok(None);
+    }
+    health.lock().unwrap().dead = true;
+    Ok(Some(message))
+}
+
+/// The fields of the Client Status packet.
+pub fn write_client_status(buffer: &mut Vec<u8>, action: i32) -> io::Result<()> {
+    write_var_int(buffer, action)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::codec::{write_float, write_int, write_string};
+
+    fn health_packet(value: f32, food: i32) -> Vec<u8> {
+        let mut buffer: Vec<u8> = Vec::new();
+        write_float(&mut buffer, value).unwrap();
+        write_var_int(&mut buffer, food).unwrap();
+        write_float(&mut buffer, 0.5).unwrap();
+        buffer
+    }
+
+    fn death_packet(player_id: i32) -> Vec<u8> {
+        let mut buffer: Vec<u8> = Vec::new();
+        write_var_int(&mut buffer, player_id).unwrap();
+        write_int(&mut buffer, -1).unwrap();
+        write_string(&mut buffer, r#"{"translate":"death.attack.generic","with":["Steve"]}"#).unwrap();
+        buffer
+    }
+
+    #[test]
+    fn death_lasts_until_health_comes_back() {
+        let health = Arc::new(Mutex::new(Health::default()));
+        update_health(health_packet(7.5, 12), Arc::clone(&health)).unwrap();
+        let current = *health.lock().unwrap();
+        assert_eq!((current.health, current.food), (7.5, 12));
+
+        assert_eq!(death_combat_event(death_packet(8), Arc::clone(&health), Some(7)).unwrap(), None);
+        assert!(!health.lock().unwrap().dead);
+        let message = death_combat_event(death_packet(7), Arc::clone(&health), Some(7)).unwrap().unwrap();
+        assert_eq!(message.to_plain_text(), "Steve died");
+        assert!(health.lock().unwrap().dead);
+
+        assert!(!update_health(health_packet(0.0, 12), Arc::clone(&health)).unwrap());
+        assert!(health.lock().unwrap().dead);
+        update_health(health_packet(20.0, 20), Arc::clone(&health)).unwrap();
+        assert!(!health.lock().unwrap().dead);
+    }
+
+    #[test]
+    fn no_health_without_a_death_event_is_death() {
+        let health = Arc::new(Mutex::new(Health::default()));
+        assert!(update_health(health_packet(0.0, 20), Arc::clone(&health)).unwrap());
+        assert!(health.lock().unwrap().dead);
+        assert!(!update_health(health_packet(0.0, 20), Arc::clone(&health)).unwrap());
+        assert!(!update_health(health_packet(20.0, 20), Arc::clone(&health)).unwrap());
+        assert!(!health.lock().unwrap().dead);
+    }
+
+    #[test]
+    fn unknown_entity_id_counts_as_the_player() {
+        let health = Arc::new(Mutex::new(Health::default()));
+        assert!(death_combat_event(death_packet(3), Arc::clone(&health), None).unwrap().is_some());
+        assert!(death_combat_event(vec![0x03], health, None).is_err());
+    }
+}
@@ -0,0 +1,104 @@
+use std::collections::HashMap;
+use std::fs;
+use std::fs::File;
+use std::io;
+use std::io::ErrorKind;
+use serde::{Deserialize, Serialize};
+use crate::uuid::Uuid;
+
+/// The last time a player was seen on a server.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Sighting {
+    pub username: String,
+    /// Seconds since the Unix epoch.
+    pub last_seen: u64,
+    /// How long their last finished session was, in seconds.
+    pub last_session: Option<u64>,
+}
+
+/// When each player was last online on each server, kept in a JSON file
+/// keyed by server address and then by UUID.
+pub struct History {
+    path: String,
+    servers: HashMap<String, HashMap<String, Sighting>>,
+}
+
+impl History {
+    /// Reads the history file, a missing file is an empty history.
+    pub fn load(path: &str) -> io::Result<History> {
+        let servers = match File::open(path) {
+            Ok(file) => serde_json::from_reader(file)?,
+            Err(error) if error.kind() == ErrorKind::NotFound => HashMap::new(),
+            Err(error) => return Err(error),
+        };
+        Ok(History {
+            path: path.to_string(),
+            servers,
+        })
+    }
+
+    pub fn save(&self) -> io::Result<()> {
+        // Written next to the file and renamed, so a crash can not leave half
+        // a history behind.
+        let temporary = format!("{}.tmp", self.path);
+        fs::write(&temporary, serde_json::to_string_pretty(&self.servers)?)?;
+        fs::rename(&temporary, &self.path)
+    }
+
+    pub fn record(&mut self, server: &str, uuid: Uuid, username: &str, time: u64, session: Option<u64>) {
+        let sightings = self.servers.entry(server.to_string()).or_default();
+        let last_session = session.or_else(|| sightings.get(&uuid.to_string()).and_then(|sighting| sighting.last_session));
+        sightings.insert(
+            uuid.to_string(),
+            Sighting {
+                username: username.to_string(),
+                last_seen: time,
+                last_session,
+            },
+        );
+    }
+
+    /// Looks a player up by UUID or by username. Names can change hands, so
+    /// the most recent sighting of a name wins.
+    pub fn find(&self, server: &str, query: &str) -> Option<(Uuid, &Sighting)> {
+        let sightings = self.servers.get(server)?;
+        if let Ok(uuid) = query.parse::<Uuid>() {
+            return sightings.get(&uuid.to_string()).map(|sighting| (uuid, sighting));
+        }
+        sightings
+            .iter()
+            .filter(|(_, sighting)| sighting.username.eq_ignore_ascii_case(query))
+            .max_by_key(|(_, sighting)| sighting.last_seen)
+            .and_then(|(uuid, sighting)| Some((uuid.parse().ok()?, sighting)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+
+    #[test]
+    fn sightings_are_kept_per_server_and_saved() {
+        let path = env::temp_dir().join(format!("history_test_{}.json", std::process::id()));
+        let path = path.to_str().unwrap();
+        let mut history = History::load(path).unwrap();
+        let (alex, old_alex) = (Uuid(1), Uuid(2));
+        history.record("a:25565", old_alex, "Alex", 100, Some(50));
+        history.record("a:25565", alex, "Alex", 200, None);
+        history.record("a:25565", alex, "Alex", 300, Some(100));
+        history.record("a:25565", alex, "Alex", 400, None);
+        history.record("b:25565", Uuid(3), "Steve", 500, None);
+        history.save().unwrap();
+
+        let history = History::load(path).unwrap();
+        let (uuid, sighting) = history.find("a:25565", "alex").unwrap();
+        assert_eq!(uuid, alex);
+        assert_eq!(sighting.last_seen, 400);
+        assert_eq!(sighting.last_session, Some(100));
+        assert_eq!(history.find("a:25565", &old_alex.to_string()).unwrap().1.last_seen, 100);
+        assert!(history.find("a:25565", "Steve").is_none());
+        assert!(history.find("c:25565", "Alex").is_none());
+        fs::remove_file(path).unwrap();
+    }
+}
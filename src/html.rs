@@ -0,0 +1,128 @@
+use crate::component::{ClickAction, Component, Span};
+
+pub fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+fn span_to_html(span: &Span) -> String {
+    let style = &span.style;
+    let mut css = Vec::<String>::new();
+    if let Some(color) = style.color {
+        css.push(format!("color:#{:02x}{:02x}{:02x}", color.0[0], color.0[1], color.0[2]));
+    }
+    if style.bold == Some(true) {
+        css.push("font-weight:bold".to_string());
+    }
+    if style.italic == Some(true) {
+        css.push("font-style:italic".to_string());
+    }
+    let mut decorations = Vec::<&str>::new();
+    if style.underlined == Some(true) {
+        decorations.push("underline");
+    }
+    if style.strikethrough == Some(true) {
+        decorations.push("line-through");
+    }
+    if !decorations.is_empty() {
+        css.push(format!("text-decoration:{}", decorations.join(" ")));
+    }
+
+    let text = if style.obfuscated == Some(true) {
+        span.text.chars().map(|c| if c.is_whitespace() { c } else { '▒' }).collect()
+    } else {
+        span.text.clone()
+    };
+    let mut html = format!("<span style=\"{}\"", css.join(";"));
+    if let Some(hover_event) = &style.hover_event {
+        html.push_str(&format!(" title=\"{}\"", escape(&hover_event.to_component().to_plain_text())));
+    }
+    html.push_str(&format!(">{}</span>", escape(&text)));
+
+    match &style.click_event {
+        Some(click_event)
+            if click_event.action == ClickAction::OpenUrl
+                && (click_event.value.starts_with("http://") || click_event.value.starts_with("https://")) =>
+        {
+            format!("<a href=\"{}\">{}</a>", escape(&click_event.value), html)
+        }
+        _ => html,
+    }
+}
+
+/// Renders a component as HTML with inline CSS. Web links become links and
+/// hover texts become tooltips.
+pub fn to_html(component: &Component) -> String {
+    component.spans().iter().map(span_to_html).collect()
+}
+
+/// A self-contained HTML page of chat lines, each given as its time and
+/// message.
+pub fn html_document(title: &str, lines: &[(String, Component)]) -> String {
+    let mut html = String::new();
+    html.push_str("<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n");
+    html.push_str(&format!("<title>{}</title>\n", escape(title)));
+    html.push_str("<style>\n");
+    html.push_str("body { background: #1e1e1e; color: #ffffff; font-family: monospace; }\n");
+    html.push_str("p { margin: 0; white-space: pre-wrap; }\n");
+    html.push_str(".time { color: #808080; }\n");
+    html.push_str("a { color: inherit; }\n");
+    html.push_str("</style>\n</head>\n<body>\n");
+    for (time, message) in lines {
+        html.push_str(&format!("<p><span class=\"time\">[{}]</span> {}</p>\n", escape(time), to_html(message)));
+    }
+    html.push_str("</body>\n</html>\n");
+    html
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn styles_links_and_tooltips() {
+        let message: Component = serde_json::from_value(json!({
+            "text": "<b>", "color": "red", "bold": true, "underlined": true, "strikethrough": true,
+            "extra": [{
+                "text": "site", "color": "reset",
+                "clickEvent": {"action": "open_url", "value": "https://example.com/?a=1&b=2"},
+                "hoverEvent": {"action": "show_text", "contents": "\"go\""},
+            }],
+        }))
+        .unwrap();
+        assert_eq!(
+            to_html(&message),
+            "<span style=\"color:#ff5555;font-weight:bold;text-decoration:underline line-through\">&lt;b&gt;</span>\
+             <a href=\"https://example.com/?a=1&amp;b=2\"><span style=\"color:#ff5555;font-weight:bold;\
+             text-decoration:underline line-through\" title=\"&quot;go&quot;\">site</span></a>"
+        );
+    }
+
+    #[test]
+    fn only_web_links_are_links() {
+        let message: Component = serde_json::from_value(json!({
+            "text": "x", "clickEvent": {"action": "open_url", "value": "javascript:alert(1)"},
+        }))
+        .unwrap();
+        assert_eq!(to_html(&message), "<span style=\"\">x</span>");
+    }
+
+    #[test]
+    fn document_is_self_contained() {
+        let document = html_document("Chat", &[("12:00:00".to_string(), Component::text("hi"))]);
+        assert!(document.starts_with("<!DOCTYPE html>"));
+        assert!(document.contains("<p><span class=\"time\">[12:00:00]</span> <span style=\"\">hi</span></p>"));
+        assert!(!document.contains("<link") && !document.contains("<script"));
+    }
+}
@@ -0,0 +1,94 @@
+use std::collections::VecDeque;
+use crate::component::{ClickEvent, HoverEvent, Style};
+
+/// How many numbered parts are remembered before the oldest are forgotten.
+const MAX_INTERACTIONS: usize = 200;
+
+/// The click and hover events of a part of a chat message.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Interaction {
+    pub click_event: Option<ClickEvent>,
+    pub hover_event: Option<HoverEvent>,
+}
+
+impl Interaction {
+    pub fn of(style: &Style) -> Option<Interaction> {
+        if style.click_event.is_none() && style.hover_event.is_none() {
+            return None;
+        }
+        Some(Interaction {
+            click_event: style.click_event.clone(),
+            hover_event: style.hover_event.clone(),
+        })
+    }
+}
+
+/// The clickable and hoverable parts of recent chat messages, numbered in the
+/// order they were printed so they can be used with `/click` and `/hover`.
+pub struct Interactions {
+    entries: VecDeque<(usize, Interaction)>,
+    next_id: usize,
+}
+
+impl Interactions {
+    pub fn new() -> Interactions {
+        Interactions {
+            entries: VecDeque::new(),
+            next_id: 1,
+        }
+    }
+
+    pub fn register(&mut self, interaction: Interaction) -> usize {
+        if self.entries.len() == MAX_INTERACTIONS {
+            self.entries.pop_front();
+        }
+        let id = self.next_id;
+        self.next_id += 1;
+        self.entries.push_back((id, interaction));
+        id
+    }
+
+    pub fn get(&self, id: usize) -> Option<&Interaction> {
+        self.entries.iter().find(|(entry_id, _)| *entry_id == id).map(|(_, interaction)| interaction)
+    }
+}
+
+impl Default for Interactions {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::component::ClickAction;
+
+    fn click(value: &str) -> Interaction {
+        Interaction {
+            click_event: Some(ClickEvent {
+                action: ClickAction::RunCommand,
+                value: value.to_string(),
+            }),
+            hover_event: None,
+        }
+    }
+
+    #[test]
+    fn oldest_interactions_are_forgotten() {
+        let mut interactions = Interactions::new();
+        assert_eq!(interactions.register(click("/first")), 1);
+        for i in 0..MAX_INTERACTIONS {
+            interactions.register(click(&i.to_string()));
+        }
+        assert!(interactions.get(1).is_none());
+        assert_eq!(interactions.get(2), Some(&click("0")));
+        assert_eq!(interactions.get(MAX_INTERACTIONS + 1), Some(&click(&(MAX_INTERACTIONS - 1).to_string())));
+        assert!(interactions.get(MAX_INTERACTIONS + 2).is_none());
+    }
+
+    #[test]
+    fn plain_styles_have_no_interaction() {
+        assert!(Interaction::of(&Style::default()).is_none());
+    }
+}
@@ -0,0 +1,143 @@
+use std::collections::HashMap;
+use std::fs::File;
+use std::io;
+use std::sync::OnceLock;
+
+// The chat related part of the vanilla en_us table: chat types, join/leave,
+// deaths, advancements, disconnect reasons, common command feedback and the
+// names of the keys bound by default. Registry names it lacks are made from
+// their ids, see `registry_name`.
+const EN_US: &str = include_str!("../assets/lang/en_us.json");
+
+static LANGUAGE: OnceLock<HashMap<String, String>> = OnceLock::new();
+
+fn bundled() -> HashMap<String, String> {
+    serde_json::from_str(EN_US).expect("bundled en_us.json is valid")
+}
+
+/// Loads a locale file (a vanilla `<locale>.json`) over the bundled part of
+/// the en_us table, so keys the locale lacks still resolve in English. The table is
+/// parsed once; call this before the first message is translated.
+pub fn load_language(path: Option<&str>) -> io::Result<()> {
+    let mut table = bundled();
+    if let Some(path) = path {
+        let file = File::open(path)?;
+        let locale: HashMap<String, String> = serde_json::from_reader(file)?;
+        table.extend(locale);
+    }
+    if LANGUAGE.set(table).is_err() {
+        return Err(io::Error::other("Language table is already loaded"));
+    }
+    Ok(())
+}
+
+pub fn get(key: &str) -> Option<&'static str> {
+    LANGUAGE.get_or_init(bundled).get(key).map(String::as_str)
+}
+
+/// Kinds of registry names whose key ends in the id, like
+/// `item.minecraft.diamond_sword`.
+const REGISTRIES: [&str; 6] = ["item", "block", "entity", "effect", "enchantment", "biome"];
+
+/// A name for an item, block or other registry entry the bundled table
+/// lacks, made from its id the way most vanilla names read:
+/// `item.minecraft.diamond_sword` is `Diamond Sword`.
+pub fn registry_name(key: &str) -> Option<String> {
+    let (registry, id) = key.split_once(".minecraft.")?;
+    let is_id = !id.is_empty() && id.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_');
+    if !REGISTRIES.contains(&registry) || !is_id {
+        return None;
+    }
+    let words: Vec<String> = id
+        .split('_')
+        .filter(|word| !word.is_empty())
+        .map(|word| word[..1].to_uppercase() + &word[1..])
+        .collect();
+    Some(words.join(" "))
+}
+
+/// The keys of the default controls, for keybind components.
+const DEFAULT_KEYS: [(&str, &str); 33] = [
+    ("key.advancements", "key.keyboard.l"),
+    ("key.attack", "key.mouse.left"),
+    ("key.back", "key.keyboard.s"),
+    ("key.chat", "key.keyboard.t"),
+    ("key.command", "key.keyboard.slash"),
+    ("key.drop", "key.keyboard.q"),
+    ("key.forward", "key.keyboard.w"),
+    ("key.fullscreen", "key.keyboard.f11"),
+    ("key.hotbar.1", "key.keyboard.1"),
+    ("key.hotbar.2", "key.keyboard.2"),
+    ("key.hotbar.3", "key.keyboard.3"),
+    ("key.hotbar.4", "key.keyboard.4"),
+    ("key.hotbar.5", "key.keyboard.5"),
+    ("key.hotbar.6", "key.keyboard.6"),
+    ("key.hotbar.7", "key.keyboard.7"),
+    ("key.hotbar.8", "key.keyboard.8"),
+    ("key.hotbar.9", "key.keyboard.9"),
+    ("key.inventory", "key.keyboard.e"),
+    ("key.jump", "key.keyboard.space"),
+    ("key.left", "key.keyboard.a"),
+    ("key.loadToolbarActivator", "key.keyboard.x"),
+    ("key.pickItem", "key.mouse.middle"),
+    ("key.playerlist", "key.keyboard.tab"),
+    ("key.right", "key.keyboard.d"),
+    ("key.saveToolbarActivator", "key.keyboard.c"),
+    ("key.screenshot", "key.keyboard.f2"),
+    ("key.smoothCamera", "key.keyboard.unknown"),
+    ("key.sneak", "key.keyboard.left.shift"),
+    ("key.socialInteractions", "key.keyboard.p"),
+    ("key.spectatorOutlines", "key.keyboard.unknown"),
+    ("key.sprint", "key.keyboard.left.control"),
+    ("key.swapOffhand", "key.keyboard.f"),
+    ("key.togglePerspective", "key.keyboard.f5"),
+];
+
+/// The name of the key a control is bound to by default, like `Space` for
+/// `key.jump`. Letter and symbol keys have no translation, the game shows
+/// the character itself. Unknown controls are shown as they are.
+pub fn key_name(keybind: &str) -> String {
+    let Some((_, key)) = DEFAULT_KEYS.iter().find(|(name, _)| *name == keybind) else {
+        return keybind.to_string();
+    };
+    if let Some(name) = get(key) {
+        return name.to_string();
+    }
+    match key.strip_prefix("key.keyboard.") {
+        Some("slash") => "/".to_string(),
+        Some(character) => character.to_uppercase(),
+        None => key.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bundled_table_has_chat_keys() {
+        assert_eq!(get("multiplayer.player.joined"), Some("%s joined the game"));
+        assert_eq!(get("chat.type.text"), Some("<%s> %s"));
+        assert_eq!(get("no.such.key"), None);
+    }
+
+    #[test]
+    fn registry_names_come_from_the_id() {
+        assert_eq!(registry_name("item.minecraft.diamond_sword").as_deref(), Some("Diamond Sword"));
+        assert_eq!(registry_name("block.minecraft.oak_planks").as_deref(), Some("Oak Planks"));
+        assert_eq!(registry_name("entity.minecraft.zombie").as_deref(), Some("Zombie"));
+        assert_eq!(registry_name("item.minecraft.potion.effect.water"), None);
+        assert_eq!(registry_name("gui.minecraft.done"), None);
+        assert_eq!(registry_name("item.minecraft."), None);
+    }
+
+    #[test]
+    fn default_key_names() {
+        assert_eq!(key_name("key.jump"), "Space");
+        assert_eq!(key_name("key.forward"), "W");
+        assert_eq!(key_name("key.command"), "/");
+        assert_eq!(key_name("key.hotbar.3"), "3");
+        assert_eq!(key_name("key.smoothCamera"), "Not Bound");
+        assert_eq!(key_name("key.custom"), "key.custom");
+    }
+}
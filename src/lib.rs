@@ -0,0 +1,22 @@
+pub mod boss_bar;
+pub mod chat;
+pub mod chat_log;
+pub mod client_settings;
+pub mod codec;
+pub mod component;
+pub mod config;
+pub mod frame;
+pub mod health;
+pub mod history;
+pub mod html;
+pub mod interactions;
+pub mod lang;
+pub mod movement;
+pub mod outgoing;
+pub mod players;
+pub mod scoreboard;
+pub mod teams;
+pub mod text_formatting;
+pub mod time;
+pub mod titles;
+pub mod uuid;
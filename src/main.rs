@@ -1,188 +1,41 @@
 use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
 use std::fs::File;
 use std::io;
+use std::io::BufReader;
 use std::io::Cursor;
 use std::io::Read;
 use std::io::Write;
-use std::io::{Error, ErrorKind};
 use std::net::TcpStream;
-use std::vec;
-use yazi::{Decoder, Format, Adler32};
 use std::thread;
+use std::time::Duration;
 use std::sync::{Arc, Mutex};
-mod text_formatting;
-
-#[derive(Clone)]
-struct PlayerInfo {
-    uuid: Vec<u8>,
-    username: String,
-    ping: i32,
-}
-
-impl PlayerInfo {
-    fn new() -> PlayerInfo {
-        PlayerInfo {
-            uuid: Vec::new(),
-            username: String::new(),
-            ping: 0,
-        }
-    }
-}
-
-#[derive(Clone)]
-struct Players {
-    players : Vec<PlayerInfo>,
-}
-
-impl Players {
-    fn new() -> Players {
-        Players {
-            players: Vec::new(),
-        }
-    }
-
-    fn register (&mut self, player: PlayerInfo) {
-        if let Some(index) = self.players.iter_mut().find(|p| p.uuid == player.uuid) {
-            index.ping = player.ping;
-        }
-        else {
-            self.players.push(player);
-        }
-    }
-
-    fn update_ping (&mut self, uuid: Vec<u8>, ping: i32) {
-        if let Some(pos) = self.players.iter().position(|p| p.uuid == uuid) {
-            self.players[pos].ping = ping;
-        }
-    }
-
-    fn remove_player (&mut self, uuid: Vec<u8>) {
-        if let Some(pos) = self.players.iter().position(|p| p.uuid == uuid) {
-            self.players.remove(pos);
-        }
-    }
-
-    pub fn print_all_players(&self) {
-        for player in &self.players {
-            println!("Username: {}  ping: {}", player.username, player.ping);
-        }
-    }
-}
-
-const SEGMENT_BITS: u8 = 0b0111_1111;
-const CONTINUE_BIT: u8 = 0b1000_0000;
-
-fn write_var_int(buffer: &mut Vec<u8>, mut value: i32) -> io::Result<()> {
-    loop {
-        if (value & !(SEGMENT_BITS as i32)) == 0 {
-            buffer.push(value as u8);
-            return Ok(());
-        }
-
-        buffer.push((value & SEGMENT_BITS as i32) as u8 | CONTINUE_BIT);
-        value = ((value as u32) >> 7) as i32;
-    }
-}
-
-fn read_var_int(buffer: &mut [u8]) -> io::Result<Vec<i32>> {
-    let mut value: i32 = 0;
-    let mut position: i32 = 0;
-    let mut current_byte: u8;
-    let mut next_byte: u8 = 0;
-
-    for (i, item) in buffer.iter().enumerate() {
-        next_byte = i as u8;
-        current_byte = *item;
-        value |= ((current_byte & SEGMENT_BITS) as i32) << position;
-
-        if ((current_byte & CONTINUE_BIT) as i32) == 0 {
-            break;
-        }
-
-        position += 7;
-
-        if position >= 32 {
-            return Err(Error::new(ErrorKind::Other, "VarInt is too big"));
-        }
-    }
-
-    next_byte += 1;
-
-    Ok(vec![value, next_byte as i32])
-}
-
-fn read_var_int_from_stream(mut stream: &TcpStream) -> io::Result<i32> {
-    let mut value: i32 = 0;
-    let mut position: i32 = 0;
-    let mut current_byte: [u8; 1] = [0];
-    //let mut nr_bytes: i32 = 0;
-
-    loop {
-        stream.read_exact(&mut current_byte)?;
-        //nr_bytes = nr_bytes + 1;
-        value |= ((current_byte[0] & SEGMENT_BITS) as i32) << position;
-
-        if ((current_byte[0] & CONTINUE_BIT) as i32) == 0 {
-            break;
-        }
-
-        position += 7;
-
-        if position >= 32 {
-            return Err(Error::new(ErrorKind::Other, "VarInt from is too big"));
-        }
-    }
-
-    Ok(value)
-}
-
-/* 
-fn write_var_long(buffer: &mut Vec<u8>, mut value: i64) -> io::Result<()> {
-    loop {
-        if (value & !(SEGMENT_BITS as i64)) == 0 {
-            buffer.push(value as u8);
-            return Ok(());
-        }
-
-        buffer.push((value & SEGMENT_BITS as i64) as u8 | CONTINUE_BIT);
-        value = ((value as u64) >> 7) as i64;
-    }
-}
-
-fn read_var_long(buffer: &mut Vec<u8>) -> io::Result<i64> {
-    let mut value: i64 = 0;
-    let mut position: i64 = 0;
-    let mut current_byte: u8;
-
-    for i in 0..buffer.len() {
-        current_byte = buffer[i];
-        value |= ((current_byte & SEGMENT_BITS) as i64) << position;
-
-        if ((current_byte & CONTINUE_BIT) as i64) == 0 {
-            break;
-        }
-
-        position += 7;
-
-        if position >= 64 {
-            return Err(Error::new(ErrorKind::Other, "VarLong is too big"));
-        }
-    }
-
-    return Ok(value);
-}*/
-
-fn write_string(buffer: &mut Vec<u8>, value: &str) -> io::Result<()> {
-    write_var_int(buffer, value.len() as i32)?;
-    buffer.extend_from_slice(value.as_bytes());
-    Ok(())
-}
-
-fn write_long (buffer: &mut Vec<u8>, value: i64) -> io::Result<()> {
-    let mut payload_vec: Vec<u8> = value.to_be_bytes().to_vec();
-    buffer.append(&mut payload_vec);
-    Ok(())
-}
+use proiect_rust::boss_bar::{boss_bar, BossBars};
+use proiect_rust::chat::{action_bar, chat_from_server, Chat, ChatPosition};
+use proiect_rust::chat_log;
+use proiect_rust::client_settings::{write_brand, write_client_settings};
+use proiect_rust::time;
+use proiect_rust::titles::{clear_titles, subtitle, title, title_times, Titles};
+use proiect_rust::time::{format_duration, format_time};
+use proiect_rust::chat_log::ChatLog;
+use proiect_rust::component::ClickAction;
+use proiect_rust::codec::{read_int, read_string, read_uuid, read_var_int, read_var_int_from_stream, write_long, write_string, write_var_int};
+use proiect_rust::config::Config;
+use proiect_rust::frame::{read_frame, skip_malformed, PacketDecoder};
+use proiect_rust::interactions::Interactions;
+use proiect_rust::lang::load_language;
+use proiect_rust::movement::{
+    read_position_and_look, write_entity_action, write_position_and_rotation, write_rotation, write_teleport_confirm, Presence, START_SNEAKING,
+    STOP_SNEAKING,
+};
+use proiect_rust::outgoing::{is_allowed_character, preview, split_message, strip_command_escape, MAX_CHAT_LENGTH};
+use proiect_rust::health::{death_combat_event, update_health, write_client_status, Health, RESPAWN};
+use proiect_rust::history::History;
+use proiect_rust::players::{player_info, player_list_header_footer, ListQuery, PlayerEvent, Players};
+use proiect_rust::scoreboard::{display_scoreboard, scoreboard_objective, update_score, Scoreboard};
+use proiect_rust::teams::teams;
+use proiect_rust::text_formatting::{set_render_options, to_ansi, ColorSupport, RenderOptions};
+use proiect_rust::uuid::Uuid;
 
 fn handshake_packet(id: u8, version: i32, ip: &str, port: u16, number: i32) -> io::Result<Vec<u8>> {
     let mut packet: Vec<u8> = Vec::<u8>::new();
@@ -213,7 +66,7 @@ fn read_packets(mut stream: &TcpStream) -> io::Result<()> {
     let mut buffer: Vec<u8> = Vec::<u8>::new();
     stream.read_to_end(&mut buffer)?;
     //println!("Bytes read: {}", bytes);
-    let packet_length1: Vec<i32> = read_var_int(&mut buffer)?;
+    let packet_length1: Vec<i32> = read_var_int(&buffer)?;
     let packet_size1: i32 = packet_length1[0];
     let pack1: Vec<u8> = buffer[0..(packet_size1 + packet_length1[1]) as usize].to_vec();
     let pack2: Vec<u8> = buffer[(packet_size1 + packet_length1[1]) as usize..].to_vec();
@@ -230,7 +83,7 @@ fn read_status_response(response: &mut [u8]) -> io::Result<()> {
     read_next += 1;
     let mut slice = Vec::<u8>::new();
     slice.extend_from_slice(&response[read_next as usize..]);
-    let vec2: Vec<i32> = read_var_int(&mut slice)?;
+    let vec2: Vec<i32> = read_var_int(&slice)?;
     let json_size: i32 = vec2[0];
     read_next = vec2[1];
     let mut slice2 = Vec::<u8>::new();
@@ -312,113 +165,257 @@ fn login_request() -> io::Result<Vec<u8>> {
     Ok(login_packet)
 }
 
-fn flush_bytes(mut stream: &TcpStream, nr_bytes: usize) -> io::Result<()> {
-    let mut buffer: Vec<u8> = vec![0; nr_bytes];
-    stream.read_exact(&mut buffer)?;
-    Ok(())
-}
+/// What the reading and the typing threads share.
+#[derive(Clone)]
+struct Shared {
+    all_players: Arc<Mutex<Players>>,
+    chat: Arc<Mutex<Chat>>,
+    history: Arc<Mutex<History>>,
+    scoreboard: Arc<Mutex<Scoreboard>>,
+    boss_bars: Arc<Mutex<BossBars>>,
+    presence: Arc<Mutex<Presence>>,
+    health: Arc<Mutex<Health>>,
+    config: Arc<Config>,
+    /// Every packet to the server goes through here, so packets sent from
+    /// different threads do not interleave.
+    writer: Arc<Mutex<TcpStream>>,
+}
+
+fn packet_monitoring(stream: &mut BufReader<TcpStream>, compression: i32, shared: Shared, mut chat_log: Option<ChatLog>) -> io::Result<()> {
+    let Shared { all_players, chat, history, scoreboard, boss_bars, presence, health, config, writer } = shared;
+    let mut titles = Titles::new();
+    let mut login: bool = false;
+    let mut decoder = PacketDecoder::new(compression);
+    loop {
+        let frame: Vec<u8> = read_frame(stream)?;
+        let vec = read_var_int(&frame)?;
+        let data_length: i32 = vec[0];
+        let read_next: usize = vec[1] as usize;
+        let packet: Vec<u8> = if data_length == 0 {
+            frame[read_next..].to_vec()
+        } else {
+            decoder.decode(data_length, &frame[read_next..])?
+        };
+        let vec = read_var_int(&packet)?;
+        let packet_id: i32 = vec[0];
+        let data: Vec<u8> = packet[vec[1] as usize..].to_vec();
+
+        if login && packet_id == 0x02 {
+            continue;
+        }
+        match packet_id {
+            0x02 => {
+                login=true;
+                println!("====Login_success====");
+                println!("Packet id: {}", packet_id);
+                println!("Packet size: {}", frame.len());
+                let uuid = login_success(&data)?;
+                all_players.lock().unwrap().set_own_uuid(uuid);
+            }
 
-fn packet_decoder(mut stream: &TcpStream, nr_bytes: usize) -> Result<Vec<u8>, yazi::Error> {
-    let mut buffer: Vec<u8> = vec![0; nr_bytes];
-    stream.read_exact(&mut buffer)?;
+            0x0D => {
+                skip_malformed("boss bar", boss_bar(data, Arc::clone(&boss_bars)))?;
+            }
 
-    let mut decoder = Decoder::new();
-    decoder.set_format(Format::Zlib);
+            0x0F => {
+                let chat_message = chat_from_server(data, Arc::clone(&chat), Arc::clone(&all_players));
+                let Some(chat_message) = skip_malformed("chat", chat_message)? else {
+                    continue;
+                };
+                let logged = chat.lock().unwrap().is_logged(chat_message.position);
+                if let Some(chat_log) = chat_log.as_mut().filter(|_| logged) {
+                    chat_log.append(time::now(), &chat_message.json)?;
+                }
+            }
 
-    let mut decompressed_vec = Vec::<u8>::new();
-    let mut decomp_stream = decoder.stream_into_vec(&mut decompressed_vec);
-    decomp_stream.write_all(&buffer)?;
+            0x10 => {
+                skip_malformed("clear titles", clear_titles(data, &mut titles))?;
+            }
 
-    let (_, chekcsum) = decomp_stream.finish()?;
-    if Adler32::from_buf(&decompressed_vec).finish() != chekcsum.unwrap() {
-        return Err(yazi::Error::InvalidBitstream);
-    }
-    Ok(decompressed_vec)
-}
+            0x21 => {
+                keep_alive_from_server(&writer, data)?;
+            }
 
-fn packet_monitoring(mut stream: &TcpStream, all_players: Arc<Mutex<Players>>) -> io::Result<()> {
-    let mut login: bool = false;
-    loop {
-        let packet_length: i32 = read_var_int_from_stream(stream)?;
-        let data_length: i32 = read_var_int_from_stream(stream)?;
-        let mut data_length_in_bytes: Vec<u8> = Vec::<u8>::new();
-        write_var_int(&mut data_length_in_bytes, data_length)?;
-        let bytes: usize = data_length_in_bytes.len();
-        if data_length == 0 {
-            let packet_id: i32 = read_var_int_from_stream(stream)?;
-            if login && packet_id == 0x02 {
-                flush_bytes(stream, (packet_length - 2) as usize)?;
-                continue;
+            0x26 => {
+                let entity_id = read_int(&mut Cursor::new(data))?;
+                presence.lock().unwrap().entity_id = Some(entity_id);
+                // What the game sends right after joining.
+                let mut fields: Vec<u8> = Vec::<u8>::new();
+                write_client_settings(&mut fields, &config)?;
+                send(&writer, &play_packet(0x05, fields)?)?;
+                let mut fields: Vec<u8> = Vec::<u8>::new();
+                write_brand(&mut fields, &config.brand)?;
+                send(&writer, &play_packet(0x0A, fields)?)?;
             }
-            match packet_id {
-                0x02 => {
-                    login=true;
-                    println!("====Login_success====");
-                    println!("Packet id: {}", packet_id);
-                    println!("Packet size: {}", packet_length);
-                    login_success(stream)?;
-                }
-                
-                0x0F => {
-                    let mut buffer: Vec<u8> = vec![0; (packet_length - 2) as usize];
-                    stream.read_exact(&mut buffer)?;
-                    chat_from_server(buffer)?;
-                }
 
-                0x21 => {
-                    keep_alive_from_server(stream)?;
+            0x35 => {
+                let entity_id = presence.lock().unwrap().entity_id;
+                // Update Health may have marked the death and respawned already.
+                let was_dead = health.lock().unwrap().dead;
+                if let Some(Some(message)) = skip_malformed("death", death_combat_event(data, Arc::clone(&health), entity_id))? {
+                    println!("[Death] {}", to_ansi(&message));
+                    if config.auto_respawn && !was_dead {
+                        respawn(&writer)?;
+                    }
                 }
+            }
 
-                0x36 => {
-                    let mut buffer: Vec<u8> = vec![0; (packet_length - 2) as usize];
-                    stream.read_exact(&mut buffer)?;
-                    let all_players_clone = Arc::clone(&all_players);
-                    player_info(buffer, all_players_clone)?;
+            0x36 => {
+                let all_players_clone = Arc::clone(&all_players);
+                if let Some(events) = skip_malformed("player info", player_info(data, all_players_clone))? {
+                    player_events(&events, &history, &config);
                 }
+            }
 
-                _ => {
-                    flush_bytes(stream, (packet_length - 2) as usize)?;
-                }
+            0x38 => {
+                let teleport = read_position_and_look(data)?;
+                let location = presence.lock().unwrap().teleport(&teleport);
+                let mut fields: Vec<u8> = Vec::<u8>::new();
+                write_teleport_confirm(&mut fields, teleport.teleport_id)?;
+                send(&writer, &play_packet(0x00, fields)?)?;
+                let mut fields: Vec<u8> = Vec::<u8>::new();
+                write_position_and_rotation(&mut fields, &location, true)?;
+                send(&writer, &play_packet(0x12, fields)?)?;
             }
-        }
 
-        if data_length != 0 {
-            let packet: Vec<u8> = packet_decoder(stream, packet_length as usize - bytes).unwrap();
-            let packet_id: i32 = packet[0] as i32;
-            let mut data: Vec<u8> = packet[1..].to_vec();
+            0x41 => {
+                skip_malformed("action bar", action_bar(data, Arc::clone(&chat)))?;
+            }
 
-            match packet_id {
-                0x0F => {
-                    chat_from_server(data)?; 
+            0x4C => {
+                skip_malformed("display scoreboard", display_scoreboard(data, Arc::clone(&scoreboard)))?;
+            }
+
+            0x52 => {
+                let died = skip_malformed("health", update_health(data, Arc::clone(&health)))?;
+                if died == Some(true) {
+                    println!("You are dead");
+                    if config.auto_respawn {
+                        respawn(&writer)?;
+                    }
                 }
+            }
+
+            0x53 => {
+                skip_malformed("scoreboard objective", scoreboard_objective(data, Arc::clone(&scoreboard)))?;
+            }
 
-                0x36 => {
-                    println!("here");
-                    let all_players_clone = Arc::clone(&all_players);
-                    player_info(data, all_players_clone)?;
+            0x55 => {
+                skip_malformed("teams", teams(data, Arc::clone(&all_players)))?;
+            }
+
+            0x56 => {
+                skip_malformed("score", update_score(data, Arc::clone(&scoreboard)))?;
+            }
+
+            0x58 => {
+                skip_malformed("subtitle", subtitle(data, &mut titles))?;
+            }
+
+            0x5A => {
+                if let Some(line) = skip_malformed("title", title(data, &mut titles))? {
+                    println!("{}", to_ansi(&line));
                 }
+            }
+
+            0x5B => {
+                skip_malformed("title times", title_times(data, &mut titles))?;
+            }
+
+            0x5F => {
+                skip_malformed("header and footer", player_list_header_footer(data, Arc::clone(&all_players)))?;
+            }
+
+            _ => {}
+        }
+    }
+}
 
-                _ => {
-                    data.clear();
+/// Shows who joined or left and remembers when they were last online.
+fn player_events(events: &[PlayerEvent], history: &Mutex<History>, config: &Config) {
+    if events.is_empty() {
+        return;
+    }
+    let mut history = history.lock().unwrap();
+    for event in events {
+        match event {
+            PlayerEvent::Listed { uuid, username, time } => {
+                history.record(SERVER, *uuid, username, *time, None);
+            }
+            PlayerEvent::Joined { uuid, username, time } => {
+                history.record(SERVER, *uuid, username, *time, None);
+                if !config.join_format.is_empty() {
+                    println!("{}", event.format(&config.join_format));
+                }
+            }
+            PlayerEvent::Left { uuid, username, time, session } => {
+                history.record(SERVER, *uuid, username, *time, *session);
+                if !config.leave_format.is_empty() {
+                    println!("{}", event.format(&config.leave_format));
                 }
             }
         }
     }
+    save_history(&history);
+}
+
+/// Remembers everyone still online as last seen now, for when we go.
+fn record_online(all_players: &Mutex<Players>, history: &Mutex<History>) {
+    let now = time::now();
+    let mut history = history.lock().unwrap();
+    for player in all_players.lock().unwrap().players() {
+        history.record(SERVER, player.uuid(), player.username(), now, None);
+    }
+    save_history(&history);
+}
+
+// The history is not worth the connection, failing to save it is only
+// reported.
+fn save_history(history: &History) {
+    if let Err(error) = history.save() {
+        println!("Could not save the player history: {}", error);
+    }
 }
 
-fn login_success(mut stream: &TcpStream) -> io::Result<()> {
-    let mut uuid: Vec<u8> = vec![0; 16];
-    stream.read_exact(&mut uuid)?;
-    let username_size = read_var_int_from_stream(stream)?;
-    let mut buffer: Vec<u8> = vec![0; username_size as usize];
-    stream.read_exact(&mut buffer)?;
-    let username = String::from_utf8(buffer).unwrap();
-    println!("Uuid: {:02x?}", uuid);
+fn seen(query: &str, all_players: &Players, history: &History) {
+    if let Some(player) = all_players.find(query) {
+        let online_for = format_duration(time::now().saturating_sub(player.joined_at()));
+        if player.join_seen() {
+            println!("{} is online, for {}", player.username(), online_for);
+        } else {
+            println!("{} is online, for at least {}", player.username(), online_for);
+        }
+        return;
+    }
+    match history.find(SERVER, query) {
+        Some((_, sighting)) => {
+            println!(
+                "{} was last seen {} ({} ago)",
+                sighting.username,
+                format_time(sighting.last_seen),
+                format_duration(time::now().saturating_sub(sighting.last_seen))
+            );
+            if let Some(session) = sighting.last_session {
+                println!("Their last session lasted {}", format_duration(session));
+            }
+        }
+        None => println!("{} has not been seen on this server", query),
+    }
+}
+
+fn login_success(buffer: &[u8]) -> io::Result<Uuid> {
+    let mut reader = Cursor::new(buffer);
+    let uuid: Uuid = read_uuid(&mut reader)?;
+    let username = read_string(&mut reader)?;
+    println!("Uuid: {}", uuid);
     println!("Your username is: {}", username);
-    Ok(())
+    if uuid == Uuid::offline_player(&username) {
+        println!("The server is in offline mode");
+    }
+    Ok(uuid)
 }
 
-fn set_compression(stream: &TcpStream) -> io::Result<()> {
+fn set_compression<R: Read>(stream: &mut R) -> io::Result<i32> {
     let packet_size: i32 = read_var_int_from_stream(stream)?;
     let packet_id: i32 = read_var_int_from_stream(stream)?;
     let compression: i32 = read_var_int_from_stream(stream)?;
@@ -426,212 +423,322 @@ fn set_compression(stream: &TcpStream) -> io::Result<()> {
     println!("Packet id: {}", packet_id);
     println!("Packet size: {}", packet_size);
     println!("Compression: {}", compression);
-    Ok(())
+    Ok(compression)
 }
 
-fn keep_alive_from_server(mut stream: &TcpStream) -> io::Result<()> {
-    let mut keep_alive: Vec<u8> = vec![0; 8];
-    stream.read_exact(&mut keep_alive)?;
-    keep_alive_from_client(stream, &mut keep_alive)?;
+fn keep_alive_from_server(writer: &Mutex<TcpStream>, mut keep_alive: Vec<u8>) -> io::Result<()> {
+    keep_alive.truncate(8);
+    keep_alive_from_client(writer, &mut keep_alive)?;
     Ok(())
 }
 
-fn keep_alive_from_client(mut stream: &TcpStream, keep_alive: &mut Vec<u8>) -> io::Result<()> {
+fn keep_alive_from_client(writer: &Mutex<TcpStream>, keep_alive: &mut Vec<u8>) -> io::Result<()> {
     let mut buffer: Vec<u8> = Vec::<u8>::new();
     buffer.push(0x00_u8);
     buffer.push(0x0F_u8);
     buffer.append(keep_alive);
     let mut packet: Vec<u8> = packet_lenght(buffer.clone())?;
     packet.append(&mut buffer);
-    stream.write_all(&packet)?;
+    send(writer, &packet)?;
     Ok(())
 }
 
-fn read_json_from_file(file_text: &str) -> io::Result<serde_json::Value> {
-    let file = File::open(file_text)?;
-    let json = serde_json::from_reader(file)?;
-    Ok(json)
+/// An uncompressed play packet with its length, from the packet id and
+/// fields.
+fn play_packet(id: u8, mut fields: Vec<u8>) -> io::Result<Vec<u8>> {
+    let mut buffer: Vec<u8> = vec![0x00, id];
+    buffer.append(&mut fields);
+    let mut packet: Vec<u8> = packet_lenght(buffer.clone())?;
+    packet.append(&mut buffer);
+    Ok(packet)
+}
+
+fn send(writer: &Mutex<TcpStream>, packet: &[u8]) -> io::Result<()> {
+    writer.lock().unwrap().write_all(packet)
+}
+
+fn respawn(writer: &Mutex<TcpStream>) -> io::Result<()> {
+    let mut fields: Vec<u8> = Vec::<u8>::new();
+    write_client_status(&mut fields, RESPAWN)?;
+    send(writer, &play_packet(0x04, fields)?)?;
+    println!("Respawning");
+    Ok(())
+}
+
+/// How often the location is sent, the game sends it at least once a
+/// second even when standing still.
+const POSITION_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Keeps telling the server where the player is once it has placed them,
+/// and moves a little now and then if anti-AFK is on. Nothing is sent on the
+/// death screen.
+fn stay_present(shared: Shared) -> io::Result<()> {
+    let anti_afk_interval = shared.config.anti_afk_interval_ms.map(Duration::from_millis);
+    let mut since_anti_afk = Duration::ZERO;
+    loop {
+        thread::sleep(POSITION_INTERVAL);
+        if shared.health.lock().unwrap().dead {
+            continue;
+        }
+        let anti_afk = anti_afk_interval.is_some_and(|interval| {
+            since_anti_afk += POSITION_INTERVAL;
+            since_anti_afk >= interval
+        });
+
+        // Copied out so the reader thread is not kept waiting while we send.
+        let (location, entity_id, turned) = {
+            let mut presence = shared.presence.lock().unwrap();
+            let Some(location) = presence.location else {
+                continue;
+            };
+            let turned = if anti_afk { presence.anti_afk_turn() } else { None };
+            (location, presence.entity_id, turned)
+        };
+        let mut fields: Vec<u8> = Vec::<u8>::new();
+        write_position_and_rotation(&mut fields, &location, true)?;
+        send(&shared.writer, &play_packet(0x12, fields)?)?;
+
+        if !anti_afk {
+            continue;
+        }
+        since_anti_afk = Duration::ZERO;
+        if let Some(location) = turned {
+            let mut fields: Vec<u8> = Vec::<u8>::new();
+            write_rotation(&mut fields, &location, true)?;
+            send(&shared.writer, &play_packet(0x13, fields)?)?;
+        }
+        if let Some(entity_id) = entity_id {
+            for action in [START_SNEAKING, STOP_SNEAKING] {
+                let mut fields: Vec<u8> = Vec::<u8>::new();
+                write_entity_action(&mut fields, entity_id, action)?;
+                send(&shared.writer, &play_packet(0x1B, fields)?)?;
+            }
+        }
+    }
+}
+
+fn f1(stream: &mut BufReader<TcpStream>, compression: i32, shared: Shared, chat_log: Option<ChatLog>) -> io::Result<()> {
+    let (all_players, history) = (Arc::clone(&shared.all_players), Arc::clone(&shared.history));
+    let result = packet_monitoring(stream, compression, shared, chat_log);
+    record_online(&all_players, &history);
+    result
+}
+
+fn chat_message_packet(msg: &str) -> io::Result<Vec<u8>> {
+    let mut buffer: Vec<u8> = Vec::<u8>::new();
+    buffer.push(0x00);
+    buffer.push(0x03_u8);
+    write_var_int(&mut buffer, msg.len() as i32)?;
+    buffer.append(&mut msg.as_bytes().to_vec());
+    let mut packet: Vec<u8> = packet_lenght(buffer.clone())?;
+    packet.append(&mut buffer);
+    Ok(packet)
 }
 
-fn chat_from_server(buffer: Vec<u8>) -> io::Result<()> {
-    let vec = read_var_int(&mut buffer.clone())?;
-    let nbt_size = vec[0];
-    let read_next = vec[1];
-    let nbt_vec = buffer[read_next as usize..nbt_size as usize + read_next as usize].to_vec();
-    let nbt_text: String = String::from_utf8(nbt_vec).unwrap();
-    let json: serde_json::Value = serde_json::from_str(&nbt_text).unwrap();
-    let style = text_formatting::Styles::new();
-    text_formatting::parse_json_obj(json, style)?;
-    println!();
+/// Sends a chat message or command, split into several messages if it is
+/// too long for one, with `split_delay` between them.
+fn send_chat(writer: &Mutex<TcpStream>, msg: &str, split_delay: Duration) -> io::Result<()> {
+    if msg.is_empty() {
+        return Ok(());
+    }
+    if !msg.chars().all(is_allowed_character) {
+        println!("The message has characters the server does not allow");
+        return Ok(());
+    }
+    let parts = match split_message(msg, MAX_CHAT_LENGTH) {
+        Ok(parts) => parts,
+        Err(error) => {
+            println!("{}", error);
+            return Ok(());
+        }
+    };
+    for (i, part) in parts.iter().enumerate() {
+        if i > 0 {
+            thread::sleep(split_delay);
+        }
+        send(writer, &chat_message_packet(part)?)?;
+    }
     Ok(())
 }
 
-fn f1(stream: &TcpStream, all_players: Arc<Mutex<Players>>) -> io::Result<()> {
-    packet_monitoring(stream, all_players)?;
+fn click(writer: &Mutex<TcpStream>, id: &str, interactions: &Interactions) -> io::Result<()> {
+    let click_event = match id.parse::<usize>().ok().and_then(|id| interactions.get(id)) {
+        Some(interaction) => interaction.click_event.as_ref(),
+        None => {
+            println!("No chat part is numbered {}", id);
+            return Ok(());
+        }
+    };
+    let Some(click_event) = click_event else {
+        println!("Nothing happens when {} is clicked", id);
+        return Ok(());
+    };
+
+    match click_event.action {
+        ClickAction::OpenUrl => println!("Link: {}", click_event.value),
+        ClickAction::RunCommand => send_chat(writer, &click_event.value, Duration::ZERO)?,
+        ClickAction::SuggestCommand => println!("Suggested: {}", click_event.value),
+        ClickAction::CopyToClipboard => {
+            // OSC 52 asks the terminal to set the clipboard.
+            print!("\x1b]52;c;{}\x07", STANDARD.encode(&click_event.value));
+            println!("Copied: {}", click_event.value);
+        }
+        ClickAction::OpenFile | ClickAction::ChangePage => println!("Only works in the game: {}", click_event.value),
+    }
     Ok(())
 }
 
-fn f2(stream: &TcpStream, all_players: Arc<Mutex<Players>>) -> io::Result<()> {
+fn hover(id: &str, interactions: &Interactions) {
+    match id.parse::<usize>().ok().and_then(|id| interactions.get(id)) {
+        Some(interaction) => match &interaction.hover_event {
+            Some(hover_event) => println!("{}", to_ansi(&hover_event.to_component())),
+            None => println!("{} has no hover text", id),
+        },
+        None => println!("No chat part is numbered {}", id),
+    }
+}
+
+fn f2(shared: Shared) -> io::Result<()> {
+    let Shared { all_players, chat, history, scoreboard, boss_bars, health, config, writer, .. } = shared;
+    let split_delay = Duration::from_millis(config.split_delay_ms);
     loop {
         let mut message = String::new();
         io::stdin().read_line(&mut message)?;
         message = message.trim().replace('\0', "");
         let msg = message.trim();
-        let mut buffer: Vec<u8> = Vec::<u8>::new();
-        buffer.push(0x00);
-        buffer.push(0x03_u8);
-        let msg_len = msg.len();
-        if msg_len > 256 {
-            println!("Message too long");
-            message.clear();
-            buffer.clear();
+        if let Some(verbatim) = strip_command_escape(msg) {
+            send_chat(&writer, verbatim, split_delay)?;
             continue;
         }
 
         match msg {
             "/help" => {
                 println!("===Custom_commands===");
-                println!("</all players> : prints online players");
+                println!("</players [sort:name|ping|join] [mode:gamemode] [name]> : lists online players");
+                println!("</player name|uuid> : prints an online player");
+                println!("</tab> : prints the tab list with its header and footer");
+                println!("</scoreboard [objective]> : prints the sidebar, or another objective");
+                println!("</bossbars> : prints the boss bars");
+                println!("</health> : prints your health and food");
+                println!("</respawn> : respawns after dying");
+                println!("</seen name|uuid> : tells when a player was last online on this server");
+                println!("</click n> : clicks the chat part numbered [n]");
+                println!("</hover n> : shows the hover text of the chat part numbered [n]");
+                println!("</hide chat|system|action_bar> : stops showing a type of message");
+                println!("</show chat|system|action_bar> : shows a hidden type of message again");
+                println!("</preview message> : shows how a server that supports &-codes shows a message");
+                println!("</export file.html|file.txt> : saves the chat log as a web page or as plain text");
+                println!("<\\message> : sends the message without the backslash instead of running a client command");
                 println!("</quit> : exits the application");
             }
 
             "/quit" => {
-                stream.shutdown(std::net::Shutdown::Both)?;
+                record_online(&all_players, &history);
+                writer.lock().unwrap().shutdown(std::net::Shutdown::Both)?;
                 std::process::exit(0);
             }
 
-            "/all players" => {
-                all_players.lock().unwrap().print_all_players();
+            _ if msg == "/players" || msg.starts_with("/players ") => {
+                match ListQuery::parse(&msg["/players".len()..]) {
+                    Ok(query) => all_players.lock().unwrap().print_players(&query),
+                    Err(error) => println!("{}", error),
+                }
             }
 
-            _ => {
-                write_var_int(&mut buffer, msg_len as i32)?;
-                buffer.append(&mut msg.as_bytes().to_vec());
-                let mut packet: Vec<u8> = packet_lenght(buffer.clone())?;
-                packet.append(&mut buffer);
-                let stream = Arc::new(Mutex::new(stream));
-                let mut stream = stream.lock().unwrap();
-                stream.write_all(&packet)?;
+            "/tab" => {
+                all_players.lock().unwrap().print_tab_list();
             }
-        }
-    }
-}
 
-fn player_info (mut buffer: Vec<u8>, all_players: Arc<Mutex<Players>>) -> io::Result<()> {
-    let vec = read_var_int(&mut buffer)?;
-    let action = vec[0];
-    let mut read_next = vec[1];
-    buffer = buffer[read_next as usize..].to_vec();
-    let vec = read_var_int(&mut buffer)?;
-    let nr_players = vec[0];
-    read_next = vec[1];
-    let mut player_vec = buffer[read_next as usize..].to_vec();
-    for _ in 0..nr_players {
-        let mut player: PlayerInfo = PlayerInfo::new();
-        match action {
-            0 => {
-                    player.uuid = player_vec[0..16].to_vec();
-                    player_vec = player_vec[16..].to_vec();
-                    let vec = read_var_int(&mut player_vec)?;
-                    let player_name_size = vec[0];
-                    read_next = vec[1];
-                    let player_username = player_vec[read_next as usize .. player_name_size as usize + read_next as usize].to_vec();
-                    player.username = String::from_utf8(player_username).unwrap();
-                    player_vec = player_vec[player_name_size as usize + read_next as usize..].to_vec();
-                    let vec = read_var_int(&mut player_vec)?;
-                    let nop = vec[0];
-                    read_next = vec[1];
-                    player_vec = player_vec[read_next as usize..].to_vec();
-                    for _ in 0..nop {
-                        let vec = read_var_int(&mut player_vec)?;
-                        let name_size = vec[0];
-                        read_next = vec[1];
-                        player_vec = player_vec[name_size as usize + read_next as usize..].to_vec();
-                        let vec = read_var_int(&mut player_vec)?;
-                        let value_size = vec[0];
-                        read_next = vec[1];
-                        player_vec = player_vec[value_size as usize + read_next as usize..].to_vec();
-                        let signed: u8 = player_vec[0];
-                        player_vec = player_vec[1..].to_vec();
-                        if signed == 0x01 {
-                            let vec = read_var_int(&mut player_vec)?;
-                            let signature_size = vec[0];
-                            read_next = vec[1];
-                            player_vec = player_vec[signature_size as usize + read_next as usize..].to_vec();
-                        }
-                    }
-                    let vec = read_var_int(&mut player_vec)?;
-                    read_next = vec[1];
-                    player_vec = player_vec[read_next as usize..].to_vec();
-                    let vec = read_var_int(&mut player_vec)?;
-                    let ping = vec[0];
-                    player.ping = ping;
-                    read_next = vec[1];
-                    player_vec = player_vec[read_next as usize..].to_vec();
-                    let display_name: u8 = player_vec[0];
-                    player_vec = player_vec[1..].to_vec();
-                    if display_name == 0x01 {
-                        let vec = read_var_int(&mut player_vec)?;
-                        let display_name_size = vec[0];
-                        read_next = vec[1];
-                        player_vec = player_vec[display_name_size as usize + read_next as usize..].to_vec();
-                    }
-                    all_players.lock().unwrap().register(player);
-            }
-
-            1 => {
-                player.uuid = player_vec[0..16].to_vec();
-                player_vec = player_vec[16..].to_vec();
-                let vec = read_var_int(&mut player_vec)?;
-                read_next = vec[1];
-                player_vec = player_vec[read_next as usize..].to_vec();
-            }
-
-            2 => {
-                player.uuid = player_vec[0..16].to_vec();
-                player_vec = player_vec[16..].to_vec();
-                let vec = read_var_int(&mut player_vec)?;
-                let ping = vec[0];
-                player.ping = ping;
-                read_next = vec[1];
-                player_vec = player_vec[read_next as usize..].to_vec();
-                all_players.lock().unwrap().update_ping(player.uuid.clone(), ping);
-            }
-
-            3 => {
-                player.uuid = player_vec[0..16].to_vec();
-                player_vec = player_vec[16..].to_vec();
-                let display_name: u8 = player_vec[0];
-                player_vec = player_vec[1..].to_vec();
-                if display_name == 0x01 {
-                    let vec = read_var_int(&mut player_vec)?;
-                    let display_name_size = vec[0];
-                    read_next = vec[1];
-                    player_vec = player_vec[display_name_size as usize + read_next as usize..].to_vec();
+            _ if msg == "/scoreboard" || msg.starts_with("/scoreboard ") => {
+                let objective = msg["/scoreboard".len()..].trim();
+                let objective = if objective.is_empty() { None } else { Some(objective) };
+                scoreboard.lock().unwrap().print_objective(objective, &all_players.lock().unwrap());
+            }
+
+            "/bossbars" => {
+                boss_bars.lock().unwrap().print_bars();
+            }
+
+            "/health" => {
+                health.lock().unwrap().print();
+            }
+
+            "/respawn" => {
+                if health.lock().unwrap().dead {
+                    respawn(&writer)?;
+                } else {
+                    println!("You are not dead");
                 }
             }
 
-            4 => {
-                player.uuid = player_vec[0..16].to_vec();
-                all_players.lock().unwrap().remove_player(player.uuid.clone());
-                continue;
+            _ if msg.starts_with("/seen ") => {
+                seen(msg["/seen ".len()..].trim(), &all_players.lock().unwrap(), &history.lock().unwrap());
+            }
+
+            _ if msg.starts_with("/player ") => {
+                all_players.lock().unwrap().print_player(msg["/player ".len()..].trim());
+            }
+
+            _ if msg.starts_with("/click ") => {
+                click(&writer, msg["/click ".len()..].trim(), &chat.lock().unwrap().interactions)?;
+            }
+
+            _ if msg.starts_with("/hover ") => {
+                hover(msg["/hover ".len()..].trim(), &chat.lock().unwrap().interactions);
+            }
+
+            _ if msg.starts_with("/hide ") || msg.starts_with("/show ") => {
+                let hide = msg.starts_with("/hide ");
+                match ChatPosition::parse(msg[6..].trim()) {
+                    Some(position) => chat.lock().unwrap().set_hidden(position, hide),
+                    None => println!("Message types are chat, system and action_bar"),
+                }
+            }
+
+            _ if msg.starts_with("/export ") => {
+                let output_path = msg["/export ".len()..].trim();
+                match &config.chat_log {
+                    Some(chat_log_path) => match chat_log::export(chat_log_path, output_path) {
+                        Ok(count) => println!("Exported {} messages to {}", count, output_path),
+                        Err(error) => println!("Could not export the chat log: {}", error),
+                    },
+                    None => println!("Set chat_log in {} to keep a chat log", CONFIG_FILE),
+                }
+            }
+
+            _ if msg.starts_with("/preview ") => {
+                // Previews what would be sent, so without the command escape.
+                let message = &msg["/preview ".len()..];
+                println!("{}", to_ansi(&preview(strip_command_escape(message).unwrap_or(message))));
             }
 
             _ => {
-                unreachable!();
+                send_chat(&writer, msg, split_delay)?;
             }
         }
     }
-    Ok(())
 }
 
+const CONFIG_FILE: &str = "config.json";
+const SERVER: &str = "VladMovi2.aternos.me:37266";
+/// Minecraft 1.18.1, the only version spoken. The signed chat of 1.19 and
+/// later needs a profile key certified by Mojang, which a client that logs
+/// in without authentication does not have.
+const PROTOCOL_VERSION: i32 = 757;
+
 fn main() -> io::Result<()> {
+    let config: Config = Config::load(CONFIG_FILE)?;
+    load_language(config.language_file.as_deref())?;
+    set_render_options(RenderOptions {
+        colors: ColorSupport::detect(),
+        background_shading: config.background_shading,
+    })?;
     let mut stream =
-        TcpStream::connect("VladMovi2.aternos.me:37266").expect("Could not connect to server");
+        TcpStream::connect(SERVER).expect("Could not connect to server");
     println!("Connected to server");
     let port: u16 = 37266;
     //handshake
-    let handshake: Vec<u8> = handshake_packet(0x00, 757, "VladMovi2.aternos.me", port, 1)?;
+    let handshake: Vec<u8> = handshake_packet(0x00, PROTOCOL_VERSION, "VladMovi2.aternos.me", port, 1)?;
     let handshake_lenght: Vec<u8> = packet_lenght(handshake.clone())?;
     stream.write_all(&handshake_lenght)?;
     stream.write_all(&handshake)?;
@@ -645,34 +752,55 @@ fn main() -> io::Result<()> {
     read_packets(&stream)?;
     //conection 2
     stream.shutdown(std::net::Shutdown::Both)?;
-    stream = TcpStream::connect("VladMovi2.aternos.me:37266").expect("Could not connect to server");
+    stream = TcpStream::connect(SERVER).expect("Could not connect to server");
     println!("Connected to server for login");
     //handshake next state = 2
-    let handshake2: Vec<u8> = handshake_packet(0x00, 757, "VladMovi2.aternos.me", port, 2)?;
+    let handshake2: Vec<u8> = handshake_packet(0x00, PROTOCOL_VERSION, "VladMovi2.aternos.me", port, 2)?;
     let handshake_lenght2: Vec<u8> = packet_lenght(handshake2.clone())?;
     stream.write_all(&handshake_lenght2)?;
     stream.write_all(&handshake2)?;
     //login request
     let login_request: Vec<u8> = login_request()?;
     stream.write_all(&login_request)?;
+    //every packet from here on is read through one buffer
+    let write_stream = stream.try_clone()?;
+    let mut stream = BufReader::new(stream);
     //set compression
-    set_compression(&stream)?;
+    let compression: i32 = set_compression(&mut stream)?;
     //login success
-    let write_stream = stream.try_clone()?;
     let all_players: Arc<Mutex<Players>> = Arc::new(Mutex::new(Players::new()));
-    let players1 = Arc::clone(&all_players);
+    let hidden: Vec<ChatPosition> = config.hidden_message_types.iter().filter_map(|name| ChatPosition::parse(name)).collect();
+    let chat: Arc<Mutex<Chat>> = Arc::new(Mutex::new(Chat::new(hidden)));
+    let chat_log: Option<ChatLog> = config.chat_log.as_deref().map(ChatLog::open).transpose()?;
+    let history: Arc<Mutex<History>> = Arc::new(Mutex::new(History::load(&config.history_file)?));
+    let shared = Shared {
+        all_players,
+        chat,
+        history,
+        scoreboard: Arc::new(Mutex::new(Scoreboard::new())),
+        boss_bars: Arc::new(Mutex::new(BossBars::new())),
+        presence: Arc::new(Mutex::new(Presence::new())),
+        health: Arc::new(Mutex::new(Health::default())),
+        config: Arc::new(config),
+        writer: Arc::new(Mutex::new(write_stream)),
+    };
+    let shared1 = shared.clone();
     let thread1 = thread::spawn(move || {
-        f1(&stream, players1).unwrap();
+        f1(&mut stream, compression, shared1, chat_log).unwrap();
     });
 
-    let players2 = Arc::clone(&all_players);
+    let shared2 = shared.clone();
     let thread2 = thread::spawn(move || {
+        f2(shared2).unwrap();
+    });
 
-        f2(&write_stream, players2).unwrap();
+    let thread3 = thread::spawn(move || {
+        stay_present(shared).unwrap();
     });
 
     thread1.join().unwrap();
     thread2.join().unwrap();
+    thread3.join().unwrap();
     println!("Logged in");
     Ok(())
 }
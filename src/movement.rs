@@ -0,0 +1,166 @@
+use std::io;
+use std::io::Cursor;
+use crate::codec::{read_bool, read_byte, read_double, read_float, read_var_int_from_stream, write_bool, write_double, write_float, write_var_int};
+
+/// How far the anti-AFK turn looks away, in degrees.
+pub const ANTI_AFK_TURN: f32 = 10.0;
+
+/// Entity Action ids for sneaking.
+pub const START_SNEAKING: i32 = 0;
+pub const STOP_SNEAKING: i32 = 1;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Location {
+    pub x: f64,
+    pub y: f64,
+    pub z: f64,
+    pub yaw: f32,
+    pub pitch: f32,
+}
+
+/// The clientbound Player Position And Look packet. Each bit of `flags`
+/// makes one field relative to the current location: x, y, z, yaw, pitch.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PositionAndLook {
+    pub location: Location,
+    pub flags: u8,
+    pub teleport_id: i32,
+    pub dismount: bool,
+}
+
+pub fn read_position_and_look(buffer: Vec<u8>) -> io::Result<PositionAndLook> {
+    let mut reader = Cursor::new(buffer);
+    let location = Location {
+        x: read_double(&mut reader)?,
+        y: read_double(&mut reader)?,
+        z: read_double(&mut reader)?,
+        yaw: read_float(&mut reader)?,
+        pitch: read_float(&mut reader)?,
+    };
+    Ok(PositionAndLook {
+        location,
+        flags: read_byte(&mut reader)?,
+        teleport_id: read_var_int_from_stream(&mut reader)?,
+        dismount: read_bool(&mut reader)?,
+    })
+}
+
+/// Where the player is in the world, as far as the server knows. There is
+/// no location until the server first teleports the player after joining.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Presence {
+    pub entity_id: Option<i32>,
+    pub location: Option<Location>,
+    /// Whether the last anti-AFK turn looked away, the next one turns back.
+    turned: bool,
+}
+
+impl Presence {
+    pub fn new() -> Presence {
+        Presence::default()
+    }
+
+    /// Moves the player where the server says and returns the new location.
+    pub fn teleport(&mut self, teleport: &PositionAndLook) -> Location {
+        let current = self.location.unwrap_or_default();
+        let relative = |bit: u8| teleport.flags & bit != 0;
+        let target = teleport.location;
+        let location = Location {
+            x: if relative(0x01) { current.x + target.x } else { target.x },
+            y: if relative(0x02) { current.y + target.y } else { target.y },
+            z: if relative(0x04) { current.z + target.z } else { target.z },
+            yaw: if relative(0x08) { current.yaw + target.yaw } else { target.yaw },
+            pitch: if relative(0x10) { current.pitch + target.pitch } else { target.pitch },
+        };
+        self.location = Some(location);
+        self.turned = false;
+        location
+    }
+
+    /// Looks a little away, or back where the player was looking.
+    pub fn anti_afk_turn(&mut self) -> Option<Location> {
+        let location = self.location.as_mut()?;
+        location.yaw += if self.turned { -ANTI_AFK_TURN } else { ANTI_AFK_TURN };
+        self.turned = !self.turned;
+        Some(*location)
+    }
+}
+
+/// The fields of the Teleport Confirm packet.
+pub fn write_teleport_confirm(buffer: &mut Vec<u8>, teleport_id: i32) -> io::Result<()> {
+    write_var_int(buffer, teleport_id)
+}
+
+/// The fields of the Player Position And Rotation packet.
+pub fn write_position_and_rotation(buffer: &mut Vec<u8>, location: &Location, on_ground: bool) -> io::Result<()> {
+    write_double(buffer, location.x)?;
+    write_double(buffer, location.y)?;
+    write_double(buffer, location.z)?;
+    write_float(buffer, location.yaw)?;
+    write_float(buffer, location.pitch)?;
+    write_bool(buffer, on_ground)
+}
+
+/// The fields of the Player Rotation packet.
+pub fn write_rotation(buffer: &mut Vec<u8>, location: &Location, on_ground: bool) -> io::Result<()> {
+    write_float(buffer, location.yaw)?;
+    write_float(buffer, location.pitch)?;
+    write_bool(buffer, on_ground)
+}
+
+/// The fields of the Entity Action packet, for actions without a jump boost.
+pub fn write_entity_action(buffer: &mut Vec<u8>, entity_id: i32, action: i32) -> io::Result<()> {
+    write_var_int(buffer, entity_id)?;
+    write_var_int(buffer, action)?;
+    write_var_int(buffer, 0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn teleport_packet(location: Location, flags: u8, teleport_id: i32) -> Vec<u8> {
+        let mut buffer: Vec<u8> = Vec::new();
+        write_position_and_rotation(&mut buffer, &location, false).unwrap();
+        // The on ground byte stands in for the flags.
+        buffer.pop();
+        buffer.push(flags);
+        write_var_int(&mut buffer, teleport_id).unwrap();
+        write_bool(&mut buffer, false).unwrap();
+        buffer
+    }
+
+    #[test]
+    fn absolute_and_relative_teleports() {
+        let mut presence = Presence::new();
+        let spawn = Location { x: 8.5, y: 64.0, z: -3.5, yaw: 90.0, pitch: 0.0 };
+        let teleport = read_position_and_look(teleport_packet(spawn, 0, 1)).unwrap();
+        assert_eq!(teleport.teleport_id, 1);
+        assert_eq!(presence.teleport(&teleport), spawn);
+
+        let step = Location { x: 1.0, y: 70.0, z: 0.0, yaw: -90.0, pitch: 30.0 };
+        let teleport = read_position_and_look(teleport_packet(step, 0x01 | 0x04 | 0x08, 2)).unwrap();
+        let location = presence.teleport(&teleport);
+        assert_eq!(location, Location { x: 9.5, y: 70.0, z: -3.5, yaw: 0.0, pitch: 30.0 });
+        assert!(read_position_and_look(vec![0; 10]).is_err());
+    }
+
+    #[test]
+    fn anti_afk_turns_away_and_back() {
+        let mut presence = Presence::new();
+        assert_eq!(presence.anti_afk_turn(), None);
+        presence.location = Some(Location::default());
+        assert_eq!(presence.anti_afk_turn().unwrap().yaw, ANTI_AFK_TURN);
+        assert_eq!(presence.anti_afk_turn().unwrap().yaw, 0.0);
+    }
+
+    #[test]
+    fn serverbound_fields() {
+        let mut buffer: Vec<u8> = Vec::new();
+        write_rotation(&mut buffer, &Location { yaw: 1.0, ..Location::default() }, true).unwrap();
+        assert_eq!(buffer, [0x3F, 0x80, 0, 0, 0, 0, 0, 0, 1]);
+        buffer.clear();
+        write_entity_action(&mut buffer, 300, START_SNEAKING).unwrap();
+        assert_eq!(buffer, [0xAC, 0x02, 0, 0]);
+    }
+}
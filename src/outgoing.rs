@@ -0,0 +1,132 @@
+use crate::component::Component;
+
+/// The longest chat message the server accepts, in UTF-16 code units.
+pub const MAX_CHAT_LENGTH: usize = 256;
+
+/// The length of a message as the server counts it: in UTF-16 code units,
+/// so characters outside the Basic Multilingual Plane count twice.
+pub fn chat_length(message: &str) -> usize {
+    message.encode_utf16().count()
+}
+
+/// The server kicks players who send `§`, control characters or DEL.
+pub fn is_allowed_character(c: char) -> bool {
+    c != '§' && c >= ' ' && c != '\u{7f}'
+}
+
+/// Splits a message into parts the server accepts, breaking at the last
+/// space that fits and inside a word only when it has none. Commands can not
+/// be split, so a command that is too long is an error.
+pub fn split_message(message: &str, max_length: usize) -> Result<Vec<String>, &'static str> {
+    if chat_length(message) <= max_length {
+        return Ok(vec![message.to_string()]);
+    }
+    if message.starts_with('/') {
+        return Err("Command too long");
+    }
+
+    let mut parts = Vec::<String>::new();
+    let mut rest = message.trim();
+    while chat_length(rest) > max_length {
+        // The byte offset where the part would go over the limit.
+        let mut length = 0;
+        let limit = rest
+            .char_indices()
+            .find(|(_, c)| {
+                length += c.len_utf16();
+                length > max_length
+            })
+            .map_or(rest.len(), |(i, _)| i);
+        let end = match rest[..limit].rfind(' ') {
+            _ if rest[limit..].starts_with(' ') => limit,
+            Some(space) if space > 0 => space,
+            // A character wider than the limit still has to go somewhere.
+            _ if limit == 0 => rest.chars().next().map_or(0, char::len_utf8),
+            _ => limit,
+        };
+        parts.push(rest[..end].trim_end().to_string());
+        rest = rest[end..].trim_start();
+    }
+    if !rest.is_empty() {
+        parts.push(rest.to_string());
+    }
+    Ok(parts)
+}
+
+/// A line starting with `\` is sent without the leading backslash and
+/// without being taken for a client command, so `\/help` sends `/help` to the
+/// server. It is only a command escape: `&` codes are always sent as typed,
+/// whether they color the message is up to the server.
+pub fn strip_command_escape(line: &str) -> Option<&str> {
+    line.strip_prefix('\\')
+}
+
+/// Shows a message the way servers that support `&` color codes will show
+/// it. `&` followed by anything but a formatting code stays as it is.
+pub fn preview(message: &str) -> Component {
+    let mut text = String::new();
+    let mut chars = message.chars().peekable();
+    while let Some(c) = chars.next() {
+        match chars.peek() {
+            Some(code) if c == '&' && "0123456789abcdefklmnorx".contains(code.to_ascii_lowercase()) => text.push('§'),
+            _ => text.push(c),
+        }
+    }
+    Component::text(&text)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::component::Color;
+    use proptest::prelude::*;
+
+    #[test]
+    fn length_is_counted_in_utf16() {
+        assert_eq!(chat_length("abc"), 3);
+        assert_eq!(chat_length("ăîș"), 3);
+        assert_eq!(chat_length("😀"), 2);
+        assert!(!is_allowed_character('§'));
+        assert!(!is_allowed_character('\n'));
+        assert!(is_allowed_character('ă'));
+    }
+
+    #[test]
+    fn long_messages_are_split_at_spaces() {
+        assert_eq!(split_message("hello world", 20).unwrap(), ["hello world"]);
+        assert_eq!(split_message("hello big world", 9).unwrap(), ["hello big", "world"]);
+        assert_eq!(split_message("abcdefghij", 4).unwrap(), ["abcd", "efgh", "ij"]);
+        assert_eq!(split_message("😀😀😀", 4).unwrap(), ["😀😀", "😀"]);
+        assert!(split_message("/say hello world", 9).is_err());
+    }
+
+    #[test]
+    fn ampersand_codes_are_previewed() {
+        let preview = preview("&cred &lbold & more &&");
+        assert_eq!(preview.to_plain_text(), "red bold & more &&");
+        let spans = preview.spans();
+        assert_eq!(spans[0].style.color, Color::parse("red"));
+        assert_eq!(spans[1].style.bold, Some(true));
+    }
+
+    #[test]
+    fn command_escape_keeps_the_rest_as_typed() {
+        assert_eq!(strip_command_escape("\\/help"), Some("/help"));
+        assert_eq!(strip_command_escape("\\\\o/"), Some("\\o/"));
+        assert_eq!(strip_command_escape("\\&cnot red?"), Some("&cnot red?"));
+        assert_eq!(strip_command_escape("/help"), None);
+        assert_eq!(preview(strip_command_escape("\\&cred").unwrap()).spans()[0].style.color, Color::parse("red"));
+    }
+
+    proptest! {
+        #[test]
+        fn split_parts_fit(message in "[a-z😀ă ]{0,300}", max_length in 2usize..64) {
+            let parts = split_message(&message, max_length).unwrap();
+            for part in &parts {
+                prop_assert!(chat_length(part) <= max_length);
+            }
+            let words = |text: &str| text.split_whitespace().map(str::to_string).collect::<String>();
+            prop_assert_eq!(words(&parts.join(" ")), words(&message));
+        }
+    }
+}
@@ -0,0 +1,894 @@
+use std::collections::VecDeque;
+use std::io;
+use std::io::Cursor;
+use std::io::{Error, ErrorKind};
+use std::sync::{Arc, Mutex};
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use serde_json::Value;
+use crate::codec::{read_bool, read_string, read_uuid, read_var_int_from_stream};
+use crate::component::{Color, Component};
+use crate::teams::Team;
+use crate::text_formatting::to_ansi;
+use crate::time;
+use crate::time::{format_duration, format_time};
+use crate::uuid::Uuid;
+
+/// How many latency updates are kept per player.
+const LATENCY_HISTORY: usize = 20;
+/// Seconds after the first player of the login list that players still
+/// count as already online, for servers that do not end it with us.
+const LOGIN_LIST_GRACE: u64 = 3;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum GameMode {
+    #[default]
+    Survival,
+    Creative,
+    Adventure,
+    Spectator,
+}
+
+impl GameMode {
+    pub fn from_id(id: i32) -> Option<GameMode> {
+        match id {
+            0 => Some(GameMode::Survival),
+            1 => Some(GameMode::Creative),
+            2 => Some(GameMode::Adventure),
+            3 => Some(GameMode::Spectator),
+            _ => None,
+        }
+    }
+
+    pub const ALL: [GameMode; 4] = [GameMode::Survival, GameMode::Creative, GameMode::Adventure, GameMode::Spectator];
+
+    pub fn parse(name: &str) -> Option<GameMode> {
+        GameMode::ALL.into_iter().find(|gamemode| gamemode.name().eq_ignore_ascii_case(name))
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            GameMode::Survival => "survival",
+            GameMode::Creative => "creative",
+            GameMode::Adventure => "adventure",
+            GameMode::Spectator => "spectator",
+        }
+    }
+}
+
+/// A profile property, like the `textures` one that holds the skin.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Property {
+    pub name: String,
+    pub value: String,
+    pub signature: Option<String>,
+}
+
+#[derive(Clone)]
+pub struct PlayerInfo {
+    uuid: Uuid,
+    username: String,
+    ping: i32,
+    gamemode: GameMode,
+    display_name: Option<Component>,
+    properties: Vec<Property>,
+    latency_history: VecDeque<i32>,
+    /// When the player joined, or for players already online when we did,
+    /// when we joined. Seconds since the Unix epoch.
+    joined_at: u64,
+    /// Whether we saw them join, so `joined_at` is when they really did.
+    join_seen: bool,
+}
+
+impl PlayerInfo {
+    pub fn new() -> PlayerInfo {
+        PlayerInfo {
+            uuid: Uuid::default(),
+            username: String::new(),
+            ping: 0,
+            gamemode: GameMode::default(),
+            display_name: None,
+            properties: Vec::new(),
+            latency_history: VecDeque::new(),
+            joined_at: 0,
+            join_seen: false,
+        }
+    }
+
+    pub fn joined_at(&self) -> u64 {
+        self.joined_at
+    }
+
+    pub fn join_seen(&self) -> bool {
+        self.join_seen
+    }
+
+    pub fn ping(&self) -> i32 {
+        self.ping
+    }
+
+    pub fn gamemode(&self) -> GameMode {
+        self.gamemode
+    }
+
+    pub fn display_name(&self) -> Option<&Component> {
+        self.display_name.as_ref()
+    }
+
+    pub fn properties(&self) -> &[Property] {
+        &self.properties
+    }
+
+    /// The display name the server set, or else the username.
+    pub fn name_component(&self) -> Component {
+        self.display_name.clone().unwrap_or_else(|| Component::text(&self.username))
+    }
+
+    fn record_ping(&mut self, ping: i32) {
+        self.ping = ping;
+        if self.latency_history.len() == LATENCY_HISTORY {
+            self.latency_history.pop_front();
+        }
+        self.latency_history.push_back(ping);
+    }
+
+    pub fn average_ping(&self) -> Option<i32> {
+        let count = self.latency_history.len() as i64;
+        (count > 0).then(|| (self.latency_history.iter().map(|&ping| ping as i64).sum::<i64>() / count) as i32)
+    }
+
+    /// The skin URL from the base64 encoded JSON of the `textures` property.
+    pub fn skin_url(&self) -> Option<String> {
+        let textures = self.properties.iter().find(|property| property.name == "textures")?;
+        let json: Value = serde_json::from_slice(&STANDARD.decode(&textures.value).ok()?).ok()?;
+        json.pointer("/textures/SKIN/url")?.as_str().map(str::to_string)
+    }
+
+    pub fn uuid(&self) -> Uuid {
+        self.uuid
+    }
+
+    pub fn username(&self) -> &str {
+        &self.username
+    }
+}
+
+impl Default for PlayerInfo {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum SortOrder {
+    #[default]
+    Name,
+    Ping,
+    JoinTime,
+}
+
+/// What `/players` shows: `[sort:name|ping|join] [mode:<game mode>] [name]`.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ListQuery {
+    pub sort: SortOrder,
+    pub gamemode: Option<GameMode>,
+    /// Part of the username, matched case-insensitively.
+    pub name: Option<String>,
+}
+
+impl ListQuery {
+    pub fn parse(arguments: &str) -> Result<ListQuery, String> {
+        let mut query = ListQuery::default();
+        for argument in arguments.split_whitespace() {
+            if let Some(sort) = argument.strip_prefix("sort:") {
+                query.sort = match sort {
+                    "name" => SortOrder::Name,
+                    "ping" => SortOrder::Ping,
+                    "join" => SortOrder::JoinTime,
+                    _ => return Err(format!("Can not sort by {}, only by name, ping or join", sort)),
+                };
+            } else if let Some(gamemode) = argument.strip_prefix("mode:") {
+                query.gamemode = Some(GameMode::parse(gamemode).ok_or_else(|| format!("Unknown game mode {}", gamemode))?);
+            } else if query.name.is_none() {
+                query.name = Some(argument.to_lowercase());
+            } else {
+                return Err(format!("Unexpected {}", argument));
+            }
+        }
+        Ok(query)
+    }
+}
+
+/// How many bars the vanilla signal icon shows for a latency, 0 for the
+/// cross of a player with no connection.
+pub fn signal_bars(ping: i32) -> usize {
+    match ping {
+        ..=-1 => 0,
+        0..=149 => 5,
+        150..=299 => 4,
+        300..=599 => 3,
+        600..=999 => 2,
+        _ => 1,
+    }
+}
+
+fn ping_bar(ping: i32) -> Component {
+    let bars = signal_bars(ping);
+    let colored = |text: &str, color: &str| {
+        let mut component = Component::text(text);
+        component.style.color = Color::parse(color);
+        component
+    };
+    if bars == 0 {
+        return colored("✗    ", "red");
+    }
+    let color = match bars {
+        4..=5 => "green",
+        3 => "yellow",
+        _ => "red",
+    };
+    Component {
+        extra: vec![colored(&"▮".repeat(bars), color), colored(&"▯".repeat(5 - bars), "dark_gray")],
+        ..Component::default()
+    }
+}
+
+#[derive(Clone)]
+pub struct Players {
+    players : Vec<PlayerInfo>,
+    teams: Vec<Team>,
+    header: Component,
+    footer: Component,
+    /// Set once the list sent at login is in, players added after that
+    /// joined while we were online.
+    listed: bool,
+    /// Our own UUID, from Login Success.
+    own_uuid: Option<Uuid>,
+    /// When the first player of the login list came in.
+    listing_started: Option<u64>,
+}
+
+impl Players {
+    pub fn new() -> Players {
+        Players {
+            players: Vec::new(),
+            teams: Vec::new(),
+            header: Component::default(),
+            footer: Component::default(),
+            listed: false,
+            own_uuid: None,
+            listing_started: None,
+        }
+    }
+
+    pub fn players(&self) -> &[PlayerInfo] {
+        &self.players
+    }
+
+    pub fn set_own_uuid(&mut self, uuid: Uuid) {
+        self.own_uuid = Some(uuid);
+    }
+
+    /// Follows the list of players sent at login. A vanilla server sends our
+    /// own entry, then one packet per player online, ending with us again.
+    /// Servers that do it differently get a grace period instead. Returns
+    /// whether a player added now joined while we were online.
+    fn joined_while_online(&mut self, uuid: Uuid, now: u64) -> bool {
+        if self.listed {
+            return true;
+        }
+        match self.listing_started {
+            None => self.listing_started = Some(now),
+            Some(started) if now >= started + LOGIN_LIST_GRACE || self.own_uuid == Some(uuid) => self.listed = true,
+            Some(_) => {}
+        }
+        false
+    }
+
+    /// Adds a player, or replaces what is known about them if they are
+    /// already listed, keeping their latency history and join time. Returns
+    /// true for a new player.
+    pub fn register (&mut self, mut player: PlayerInfo) -> bool {
+        if let Some(index) = self.players.iter_mut().find(|p| p.uuid == player.uuid) {
+            let ping = player.ping;
+            player.latency_history = std::mem::take(&mut index.latency_history);
+            player.joined_at = index.joined_at;
+            player.join_seen = index.join_seen;
+            *index = player;
+            index.record_ping(ping);
+            false
+        }
+        else {
+            let ping = player.ping;
+            player.record_ping(ping);
+            self.players.push(player);
+            true
+        }
+    }
+
+    pub fn update_ping (&mut self, uuid: Uuid, ping: i32) {
+        if let Some(pos) = self.players.iter().position(|p| p.uuid == uuid) {
+            self.players[pos].record_ping(ping);
+        }
+    }
+
+    pub fn update_gamemode (&mut self, uuid: Uuid, gamemode: GameMode) {
+        if let Some(pos) = self.players.iter().position(|p| p.uuid == uuid) {
+            self.players[pos].gamemode = gamemode;
+        }
+    }
+
+    pub fn update_display_name (&mut self, uuid: Uuid, display_name: Option<Component>) {
+        if let Some(pos) = self.players.iter().position(|p| p.uuid == uuid) {
+            self.players[pos].display_name = display_name;
+        }
+    }
+
+    pub fn remove_player (&mut self, uuid: Uuid) -> Option<PlayerInfo> {
+        let pos = self.players.iter().position(|p| p.uuid == uuid)?;
+        Some(self.players.remove(pos))
+    }
+
+    pub fn find_by_uuid(&self, uuid: Uuid) -> Option<&PlayerInfo> {
+        self.players.iter().find(|p| p.uuid == uuid)
+    }
+
+    /// Usernames are case-insensitive, like on the server.
+    pub fn find_by_name(&self, username: &str) -> Option<&PlayerInfo> {
+        self.players.iter().find(|p| p.username.eq_ignore_ascii_case(username))
+    }
+
+    /// Looks a player up by UUID if `query` is one, otherwise by username.
+    pub fn find(&self, query: &str) -> Option<&PlayerInfo> {
+        match query.parse::<Uuid>() {
+            Ok(uuid) => self.find_by_uuid(uuid),
+            Err(_) => self.find_by_name(query),
+        }
+    }
+
+    pub fn print_player(&self, query: &str) {
+        match self.find(query) {
+            Some(player) => {
+                println!("Username: {}", player.username);
+                if let Some(display_name) = &player.display_name {
+                    println!("Display name: {}", to_ansi(display_name));
+                }
+                println!("Uuid: {}", player.uuid);
+                println!("Game mode: {}", player.gamemode.name());
+                println!("Ping: {}", player.ping);
+                if let Some(average_ping) = player.average_ping() {
+                    println!("Average ping: {}", average_ping);
+                }
+                if let Some(skin_url) = player.skin_url() {
+                    println!("Skin: {}", skin_url);
+                }
+                if player.uuid == Uuid::offline_player(&player.username) {
+                    println!("Offline-mode account");
+                }
+            }
+            None => println!("No online player matches {}", query),
+        }
+    }
+
+    /// The players a query matches, in its order.
+    pub fn list(&self, query: &ListQuery) -> Vec<&PlayerInfo> {
+        let mut players: Vec<&PlayerInfo> = self
+            .players
+            .iter()
+            .filter(|player| query.gamemode.is_none_or(|gamemode| player.gamemode == gamemode))
+            .filter(|player| query.name.as_ref().is_none_or(|name| player.username.to_lowercase().contains(name)))
+            .collect();
+        match query.sort {
+            SortOrder::Name => players.sort_by_key(|player| player.username.to_lowercase()),
+            // Unknown latency, drawn as a cross, goes after every real ping.
+            SortOrder::Ping => players.sort_by_key(|player| (player.ping < 0, player.ping)),
+            SortOrder::JoinTime => players.sort_by_key(|player| player.joined_at),
+        }
+        players
+    }
+
+    pub fn print_players(&self, query: &ListQuery) {
+        let players = self.list(query);
+        for player in &players {
+            println!(
+                "{} {:>5}ms  {}  {}",
+                to_ansi(&ping_bar(player.ping)),
+                player.ping,
+                to_ansi(&self.decorated_name(player)),
+                player.gamemode.name()
+            );
+        }
+
+        let counts: Vec<String> = GameMode::ALL
+            .into_iter()
+            .map(|gamemode| (gamemode, players.iter().filter(|player| player.gamemode == gamemode).count()))
+            .filter(|(_, count)| *count > 0)
+            .map(|(gamemode, count)| format!("{} {}", count, gamemode.name()))
+            .collect();
+        if counts.is_empty() {
+            println!("{} of {} players", players.len(), self.players.len());
+        } else {
+            println!("{} of {} players ({})", players.len(), self.players.len(), counts.join(", "));
+        }
+    }
+
+    /// Adds a team, replacing one with the same name.
+    pub fn add_team(&mut self, team: Team) {
+        self.remove_team(&team.name);
+        self.teams.push(team);
+    }
+
+    pub fn remove_team(&mut self, name: &str) {
+        self.teams.retain(|team| team.name != name);
+    }
+
+    pub fn team(&self, name: &str) -> Option<&Team> {
+        self.teams.iter().find(|team| team.name == name)
+    }
+
+    pub fn team_mut(&mut self, name: &str) -> Option<&mut Team> {
+        self.teams.iter_mut().find(|team| team.name == name)
+    }
+
+    pub fn team_of(&self, username: &str) -> Option<&Team> {
+        self.teams.iter().find(|team| team.members.iter().any(|member| member == username))
+    }
+
+    /// The name the tab list shows: the display name if the server set one,
+    /// otherwise the username decorated by the player's team.
+    pub fn decorated_name(&self, player: &PlayerInfo) -> Component {
+        match (&player.display_name, self.team_of(&player.username)) {
+            (Some(display_name), _) => display_name.clone(),
+            (None, Some(team)) => team.decorate(Component::text(&player.username)),
+            (None, None) => Component::text(&player.username),
+        }
+    }
+
+    pub fn set_header_and_footer(&mut self, header: Component, footer: Component) {
+        self.header = header;
+        self.footer = footer;
+    }
+
+    /// Prints the tab list in the order of the game: spectators last, then by
+    /// team and username.
+    pub fn print_tab_list(&self) {
+        let mut players: Vec<&PlayerInfo> = self.players.iter().collect();
+        players.sort_by_key(|player| {
+            (
+                player.gamemode == GameMode::Spectator,
+                self.team_of(&player.username).map(|team| team.name.clone()).unwrap_or_default(),
+                player.username.to_lowercase(),
+            )
+        });
+
+        if self.header != Component::default() {
+            println!("{}", to_ansi(&self.header));
+        }
+        for player in players {
+            println!("{}  {}ms", to_ansi(&self.decorated_name(player)), player.ping);
+        }
+        if self.footer != Component::default() {
+            println!("{}", to_ansi(&self.footer));
+        }
+    }
+}
+
+impl Default for Players {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+pub fn player_list_header_footer(buffer: Vec<u8>, all_players: Arc<Mutex<Players>>) -> io::Result<()> {
+    let mut reader = Cursor::new(buffer);
+    let mut read_component = || -> io::Result<Component> {
+        let json = read_string(&mut reader)?;
+        serde_json::from_str(&json).map_err(|_| Error::new(ErrorKind::InvalidData, "Tab list text is not a valid chat component"))
+    };
+    let header = read_component()?;
+    let footer = read_component()?;
+    all_players.lock().unwrap().set_header_and_footer(header, footer);
+    Ok(())
+}
+
+/// Reads a game mode. Like the vanilla client, an unknown one such as the -1
+/// NPC plugins send is read as survival.
+fn read_gamemode(reader: &mut Cursor<Vec<u8>>) -> io::Result<GameMode> {
+    Ok(GameMode::from_id(read_var_int_from_stream(reader)?).unwrap_or_default())
+}
+
+fn read_display_name(reader: &mut Cursor<Vec<u8>>) -> io::Result<Option<Component>> {
+    if !read_bool(reader)? {
+        return Ok(None);
+    }
+    let json = read_string(reader)?;
+    serde_json::from_str(&json)
+        .map(Some)
+        .map_err(|_| Error::new(ErrorKind::InvalidData, "Display name is not a valid chat component"))
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum PlayerEvent {
+    /// Already online when we logged in.
+    Listed {
+        uuid: Uuid,
+        username: String,
+        time: u64,
+    },
+    Joined {
+        uuid: Uuid,
+        username: String,
+        time: u64,
+    },
+    Left {
+        uuid: Uuid,
+        username: String,
+        time: u64,
+        /// How long they were online in seconds, if we saw them join.
+        session: Option<u64>,
+    },
+}
+
+impl PlayerEvent {
+    /// Fills a format like `{name} joined at {time}`. `{duration}` is the
+    /// session length, at least that long if we did not see them join, and
+    /// is empty for joins.
+    pub fn format(&self, format: &str) -> String {
+        let (uuid, username, time, duration) = match self {
+            PlayerEvent::Listed { uuid, username, time } | PlayerEvent::Joined { uuid, username, time } => (uuid, username, time, String::new()),
+            PlayerEvent::Left { uuid, username, time, session: Some(session) } => (uuid, username, time, format_duration(*session)),
+            PlayerEvent::Left { uuid, username, time, session: None } => (uuid, username, time, "an unknown time".to_string()),
+        };
+        format
+            .replace("{name}", username)
+            .replace("{uuid}", &uuid.to_string())
+            .replace("{time}", &format_time(*time))
+            .replace("{duration}", &duration)
+    }
+}
+
+/// Applies a Player Info packet and returns who joined or left. Players in
+/// the list sent at login were already online, they are listed rather than
+/// joining.
+pub fn player_info (buffer: Vec<u8>, all_players: Arc<Mutex<Players>>) -> io::Result<Vec<PlayerEvent>> {
+    let mut events = Vec::<PlayerEvent>::new();
+    let now = time::now();
+    let mut reader = Cursor::new(buffer);
+    let action = read_var_int_from_stream(&mut reader)?;
+    let nr_players = read_var_int_from_stream(&mut reader)?;
+    for _ in 0..nr_players {
+        let mut player: PlayerInfo = PlayerInfo::new();
+        player.uuid = read_uuid(&mut reader)?;
+        match action {
+            0 => {
+                player.username = read_string(&mut reader)?;
+                let nop = read_var_int_from_stream(&mut reader)?;
+                for _ in 0..nop {
+                    let name = read_string(&mut reader)?;
+                    let value = read_string(&mut reader)?;
+                    let signature = if read_bool(&mut reader)? { Some(read_string(&mut reader)?) } else { None };
+                    player.properties.push(Property { name, value, signature });
+                }
+                player.gamemode = read_gamemode(&mut reader)?;
+                player.ping = read_var_int_from_stream(&mut reader)?;
+                player.display_name = read_display_name(&mut reader)?;
+                player.joined_at = now;
+                let (uuid, username) = (player.uuid, player.username.clone());
+                let mut all_players = all_players.lock().unwrap();
+                player.join_seen = all_players.joined_while_online(uuid, now);
+                let join_seen = player.join_seen;
+                if all_players.register(player) {
+                    events.push(if join_seen {
+                        PlayerEvent::Joined { uuid, username, time: now }
+                    } else {
+                        PlayerEvent::Listed { uuid, username, time: now }
+                    });
+                }
+            }
+
+            1 => {
+                let gamemode = read_gamemode(&mut reader)?;
+                all_players.lock().unwrap().update_gamemode(player.uuid, gamemode);
+            }
+
+            2 => {
+                player.ping = read_var_int_from_stream(&mut reader)?;
+                all_players.lock().unwrap().update_ping(player.uuid, player.ping);
+            }
+
+            3 => {
+                let display_name = read_display_name(&mut reader)?;
+                all_players.lock().unwrap().update_display_name(player.uuid, display_name);
+            }
+
+            4 => {
+                if let Some(player) = all_players.lock().unwrap().remove_player(player.uuid) {
+                    events.push(PlayerEvent::Left {
+                        uuid: player.uuid,
+                        username: player.username,
+                        time: now,
+                        session: player.join_seen.then(|| now.saturating_sub(player.joined_at)),
+                    });
+                }
+            }
+
+            _ => {
+                return Err(Error::new(ErrorKind::InvalidData, "Unknown player info action"));
+            }
+        }
+    }
+    Ok(events)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::codec::{write_string, write_var_int};
+    use proptest::prelude::*;
+
+    fn add_player(buffer: &mut Vec<u8>, uuid: u8, name: &str, ping: i32) {
+        buffer.extend_from_slice(&[uuid; 16]);
+        write_string(buffer, name).unwrap();
+        write_var_int(buffer, 1).unwrap();
+        write_string(buffer, "textures").unwrap();
+        write_string(buffer, "e30=").unwrap();
+        buffer.push(0x01);
+        write_string(buffer, "c2ln").unwrap();
+        write_var_int(buffer, 0).unwrap();
+        write_var_int(buffer, ping).unwrap();
+        buffer.push(0x00);
+    }
+
+    #[test]
+    fn add_update_and_remove_players() {
+        let all_players = Arc::new(Mutex::new(Players::new()));
+
+        let mut buffer: Vec<u8> = Vec::new();
+        write_var_int(&mut buffer, 0).unwrap();
+        write_var_int(&mut buffer, 2).unwrap();
+        add_player(&mut buffer, 1, "Alex", 20);
+        add_player(&mut buffer, 2, "Steve", 40);
+        player_info(buffer, Arc::clone(&all_players)).unwrap();
+
+        let mut buffer: Vec<u8> = Vec::new();
+        write_var_int(&mut buffer, 2).unwrap();
+        write_var_int(&mut buffer, 1).unwrap();
+        buffer.extend_from_slice(&[2; 16]);
+        write_var_int(&mut buffer, 300).unwrap();
+        player_info(buffer, Arc::clone(&all_players)).unwrap();
+
+        let mut buffer: Vec<u8> = Vec::new();
+        write_var_int(&mut buffer, 4).unwrap();
+        write_var_int(&mut buffer, 1).unwrap();
+        buffer.extend_from_slice(&[1; 16]);
+        player_info(buffer, Arc::clone(&all_players)).unwrap();
+
+        let all_players = all_players.lock().unwrap();
+        assert_eq!(all_players.players.len(), 1);
+        assert_eq!(all_players.players[0].username, "Steve");
+        assert_eq!(all_players.players[0].ping, 300);
+
+        let steve = Uuid(u128::from_be_bytes([2; 16]));
+        assert_eq!(all_players.find_by_uuid(steve).unwrap().username, "Steve");
+        assert_eq!(all_players.find("steve").unwrap().uuid, steve);
+        assert_eq!(all_players.find(&steve.to_string()).unwrap().username, "Steve");
+        assert!(all_players.find("Alex").is_none());
+    }
+
+    #[test]
+    fn gamemode_display_name_and_properties() {
+        let all_players = Arc::new(Mutex::new(Players::new()));
+        let textures = STANDARD.encode(r#"{"textures":{"SKIN":{"url":"http://textures.minecraft.net/texture/abc"}}}"#);
+
+        let mut buffer: Vec<u8> = Vec::new();
+        write_var_int(&mut buffer, 0).unwrap();
+        write_var_int(&mut buffer, 1).unwrap();
+        buffer.extend_from_slice(&[1; 16]);
+        write_string(&mut buffer, "Alex").unwrap();
+        write_var_int(&mut buffer, 1).unwrap();
+        write_string(&mut buffer, "textures").unwrap();
+        write_string(&mut buffer, &textures).unwrap();
+        buffer.push(0x00);
+        write_var_int(&mut buffer, 1).unwrap();
+        write_var_int(&mut buffer, 30).unwrap();
+        buffer.push(0x01);
+        write_string(&mut buffer, r#"{"text":"[Admin] Alex","color":"red"}"#).unwrap();
+        player_info(buffer, Arc::clone(&all_players)).unwrap();
+
+        let mut buffer: Vec<u8> = Vec::new();
+        write_var_int(&mut buffer, 1).unwrap();
+        write_var_int(&mut buffer, 1).unwrap();
+        buffer.extend_from_slice(&[1; 16]);
+        write_var_int(&mut buffer, 3).unwrap();
+        player_info(buffer, Arc::clone(&all_players)).unwrap();
+
+        let alex = Uuid(u128::from_be_bytes([1; 16]));
+        all_players.lock().unwrap().update_ping(alex, 50);
+        {
+            let all_players = all_players.lock().unwrap();
+            let player = all_players.find_by_uuid(alex).unwrap();
+            assert_eq!(player.gamemode(), GameMode::Spectator);
+            assert_eq!(player.name_component().to_plain_text(), "[Admin] Alex");
+            assert_eq!(player.properties()[0].signature, None);
+            assert_eq!(player.skin_url().as_deref(), Some("http://textures.minecraft.net/texture/abc"));
+            assert_eq!(player.average_ping(), Some(40));
+        }
+
+        let mut buffer: Vec<u8> = Vec::new();
+        write_var_int(&mut buffer, 3).unwrap();
+        write_var_int(&mut buffer, 1).unwrap();
+        buffer.extend_from_slice(&[1; 16]);
+        buffer.push(0x00);
+        player_info(buffer, Arc::clone(&all_players)).unwrap();
+
+        // NPC plugins send -1 for no game mode.
+        let mut buffer: Vec<u8> = Vec::new();
+        write_var_int(&mut buffer, 1).unwrap();
+        write_var_int(&mut buffer, 1).unwrap();
+        buffer.extend_from_slice(&[1; 16]);
+        write_var_int(&mut buffer, -1).unwrap();
+        player_info(buffer, Arc::clone(&all_players)).unwrap();
+        let all_players = all_players.lock().unwrap();
+        let player = all_players.find_by_uuid(alex).unwrap();
+        assert_eq!(player.name_component().to_plain_text(), "Alex");
+        assert_eq!(player.gamemode(), GameMode::Survival);
+    }
+
+    fn add_packet(uuid: u8, name: &str) -> Vec<u8> {
+        let mut buffer: Vec<u8> = Vec::new();
+        write_var_int(&mut buffer, 0).unwrap();
+        write_var_int(&mut buffer, 1).unwrap();
+        add_player(&mut buffer, uuid, name, 20);
+        buffer
+    }
+
+    #[test]
+    fn join_and_leave_events() {
+        let all_players = Arc::new(Mutex::new(Players::new()));
+        all_players.lock().unwrap().set_own_uuid(Uuid(u128::from_be_bytes([9; 16])));
+        // Us, everyone online one packet each, then us again.
+        for (uuid, name) in [(9, "Me"), (1, "Alex"), (3, "Herobrine")] {
+            let events = player_info(add_packet(uuid, name), Arc::clone(&all_players)).unwrap();
+            assert!(matches!(&events[..], [PlayerEvent::Listed { username, .. }] if username == name));
+        }
+        assert!(player_info(add_packet(9, "Me"), Arc::clone(&all_players)).unwrap().is_empty());
+
+        let events = player_info(add_packet(2, "Steve"), Arc::clone(&all_players)).unwrap();
+        assert_eq!(events.len(), 1);
+        assert!(matches!(&events[0], PlayerEvent::Joined { username, .. } if username == "Steve"));
+        assert!(player_info(add_packet(1, "Alex"), Arc::clone(&all_players)).unwrap().is_empty());
+
+        let mut buffer: Vec<u8> = Vec::new();
+        write_var_int(&mut buffer, 4).unwrap();
+        write_var_int(&mut buffer, 3).unwrap();
+        buffer.extend_from_slice(&[2; 16]);
+        buffer.extend_from_slice(&[1; 16]);
+        buffer.extend_from_slice(&[4; 16]);
+        let events = player_info(buffer, Arc::clone(&all_players)).unwrap();
+        assert_eq!(events.len(), 2);
+        assert!(matches!(&events[0], PlayerEvent::Left { username, session: Some(0..=5), .. } if username == "Steve"));
+        assert!(matches!(&events[1], PlayerEvent::Left { username, session: None, .. } if username == "Alex"));
+    }
+
+    #[test]
+    fn login_list_ends_after_a_grace_period() {
+        let mut players = Players::new();
+        assert!(!players.joined_while_online(Uuid(1), 100));
+        assert!(!players.joined_while_online(Uuid(2), 100 + LOGIN_LIST_GRACE - 1));
+        assert!(!players.joined_while_online(Uuid(3), 100 + LOGIN_LIST_GRACE));
+        assert!(players.joined_while_online(Uuid(4), 100 + LOGIN_LIST_GRACE));
+    }
+
+    #[test]
+    fn event_format() {
+        let left = PlayerEvent::Left {
+            uuid: Uuid(1),
+            username: "Steve".to_string(),
+            time: 3600,
+            session: Some(125),
+        };
+        assert_eq!(left.format("[{time}] {name} left after {duration}"), "[1970-01-01 01:00:00] Steve left after 2m 5s");
+        let joined = PlayerEvent::Joined {
+            uuid: Uuid(1),
+            username: "Steve".to_string(),
+            time: 0,
+        };
+        assert_eq!(joined.format("{name} ({uuid}) joined{duration}"), "Steve (00000000-0000-0000-0000-000000000001) joined");
+    }
+
+    #[test]
+    fn list_queries() {
+        let mut players = Players::new();
+        for (i, (name, ping, gamemode)) in [
+            ("steve", 300, GameMode::Creative),
+            ("Alex", 20, GameMode::Survival),
+            ("Stan", 90, GameMode::Survival),
+            ("Notch", -1, GameMode::Creative),
+        ]
+        .into_iter()
+            .enumerate()
+        {
+            let mut player = PlayerInfo::new();
+            player.uuid = Uuid(i as u128);
+            player.username = name.to_string();
+            player.ping = ping;
+            player.gamemode = gamemode;
+            player.joined_at = 10 - i as u64;
+            players.register(player);
+        }
+        let names = |query: &str| -> Vec<String> {
+            let query = ListQuery::parse(query).unwrap();
+            players.list(&query).iter().map(|player| player.username.clone()).collect()
+        };
+        assert_eq!(names(""), ["Alex", "Notch", "Stan", "steve"]);
+        assert_eq!(names("sort:ping"), ["Alex", "Stan", "steve", "Notch"]);
+        assert_eq!(names("sort:join"), ["Notch", "Stan", "Alex", "steve"]);
+        assert_eq!(names("st sort:ping"), ["Stan", "steve"]);
+        assert_eq!(names("mode:SURVIVAL st"), ["Stan"]);
+        assert!(ListQuery::parse("sort:uuid").is_err());
+        assert!(ListQuery::parse("mode:hardcore").is_err());
+        assert!(ListQuery::parse("a b").is_err());
+    }
+
+    #[test]
+    fn vanilla_signal_thresholds() {
+        let bars: Vec<usize> = [-1, 0, 149, 150, 299, 300, 599, 600, 999, 1000].into_iter().map(signal_bars).collect();
+        assert_eq!(bars, [0, 5, 5, 4, 4, 3, 3, 2, 2, 1]);
+        assert_eq!(ping_bar(450).to_plain_text(), "▮▮▮▯▯");
+    }
+
+    #[test]
+    fn latency_history_is_bounded() {
+        let mut players = Players::new();
+        let mut player = PlayerInfo::new();
+        player.ping = 1000;
+        players.register(player);
+        for _ in 0..LATENCY_HISTORY {
+            players.update_ping(Uuid::default(), 10);
+        }
+        assert_eq!(players.players[0].latency_history.len(), LATENCY_HISTORY);
+        assert_eq!(players.players[0].average_ping(), Some(10));
+    }
+
+    #[test]
+    fn header_footer_and_decorated_names() {
+        let all_players = Arc::new(Mutex::new(Players::new()));
+        let mut buffer: Vec<u8> = Vec::new();
+        write_string(&mut buffer, r#"{"text":"TPS: 20"}"#).unwrap();
+        write_string(&mut buffer, r#""""#).unwrap();
+        player_list_header_footer(buffer, Arc::clone(&all_players)).unwrap();
+        assert!(player_list_header_footer(vec![0x01, b'{'], Arc::clone(&all_players)).is_err());
+
+        let mut all_players = all_players.lock().unwrap();
+        assert_eq!(all_players.header.to_plain_text(), "TPS: 20");
+        let mut steve = PlayerInfo::new();
+        steve.username = "Steve".to_string();
+        all_players.register(steve.clone());
+        all_players.add_team(Team {
+            name: "admins".to_string(),
+            prefix: Component::text("[Admin] "),
+            members: vec!["Steve".to_string()],
+            ..Team::default()
+        });
+        assert_eq!(all_players.decorated_name(&steve).to_plain_text(), "[Admin] Steve");
+        steve.display_name = Some(Component::text("Boss"));
+        assert_eq!(all_players.decorated_name(&steve).to_plain_text(), "Boss");
+    }
+
+    #[test]
+    fn truncated_player_is_an_error() {
+        let mut buffer: Vec<u8> = Vec::new();
+        write_var_int(&mut buffer, 0).unwrap();
+        write_var_int(&mut buffer, 1).unwrap();
+        add_player(&mut buffer, 1, "Alex", 20);
+        buffer.truncate(buffer.len() - 2);
+        assert!(player_info(buffer, Arc::new(Mutex::new(Players::new()))).is_err());
+    }
+
+    proptest! {
+        #[test]
+        fn player_info_never_panics(bytes: Vec<u8>) {
+            let _ = player_info(bytes, Arc::new(Mutex::new(Players::new())));
+        }
+    }
+}
@@ -0,0 +1,290 @@
+use std::collections::HashMap;
+use std::io;
+use std::io::Cursor;
+use std::io::{Error, ErrorKind};
+use std::sync::{Arc, Mutex};
+use crate::codec::{read_byte, read_string, read_var_int_from_stream};
+use crate::component::{Color, Component};
+use crate::players::Players;
+use crate::text_formatting::to_ansi;
+
+/// The display slot of the sidebar. Slots 3 to 18 are sidebars only shown to
+/// members of teams of one color.
+pub const SIDEBAR: u8 = 1;
+/// The most lines the sidebar shows.
+pub const SIDEBAR_LINES: usize = 15;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ScoreType {
+    #[default]
+    Integer,
+    Hearts,
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Objective {
+    pub name: String,
+    pub display_name: Component,
+    pub score_type: ScoreType,
+    /// Scores by player name or entity UUID.
+    pub scores: HashMap<String, i32>,
+}
+
+impl Objective {
+    /// The scores as the sidebar lists them: highest first, ties by name.
+    /// Entries starting with `#` are hidden, like the game does.
+    pub fn sorted_scores(&self) -> Vec<(&str, i32)> {
+        let mut scores: Vec<(&str, i32)> = self
+            .scores
+            .iter()
+            .filter(|(entry, _)| !entry.starts_with('#'))
+            .map(|(entry, score)| (entry.as_str(), *score))
+            .collect();
+        scores.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(b.0)));
+        scores
+    }
+}
+
+#[derive(Clone, Default)]
+pub struct Scoreboard {
+    objectives: Vec<Objective>,
+    /// Which objective each display slot shows.
+    slots: HashMap<u8, String>,
+}
+
+impl Scoreboard {
+    pub fn new() -> Scoreboard {
+        Scoreboard::default()
+    }
+
+    pub fn objective(&self, name: &str) -> Option<&Objective> {
+        self.objectives.iter().find(|objective| objective.name == name)
+    }
+
+    pub fn objective_mut(&mut self, name: &str) -> Option<&mut Objective> {
+        self.objectives.iter_mut().find(|objective| objective.name == name)
+    }
+
+    pub fn add_objective(&mut self, objective: Objective) {
+        self.remove_objective(&objective.name);
+        self.objectives.push(objective);
+    }
+
+    pub fn remove_objective(&mut self, name: &str) {
+        self.objectives.retain(|objective| objective.name != name);
+        self.slots.retain(|_, objective| objective != name);
+    }
+
+    pub fn set_display(&mut self, slot: u8, objective: String) {
+        if objective.is_empty() {
+            self.slots.remove(&slot);
+        } else {
+            self.slots.insert(slot, objective);
+        }
+    }
+
+    pub fn displayed(&self, slot: u8) -> Option<&Objective> {
+        self.objective(self.slots.get(&slot)?)
+    }
+
+    /// Sets a score, scores of objectives that do not exist are dropped.
+    pub fn set_score(&mut self, entry: &str, objective: &str, value: i32) {
+        if let Some(objective) = self.objective_mut(objective) {
+            objective.scores.insert(entry.to_string(), value);
+        }
+    }
+
+    /// Removes a score, from every objective if none is named.
+    pub fn remove_score(&mut self, entry: &str, objective: &str) {
+        for named in self.objectives.iter_mut().filter(|named| objective.is_empty() || named.name == objective) {
+            named.scores.remove(entry);
+        }
+    }
+
+    /// Prints the sidebar, or another objective if one is named. Player
+    /// names are decorated with their team like in the game.
+    pub fn print_objective(&self, name: Option<&str>, all_players: &Players) {
+        let objective = match name {
+            Some(name) => self.objective(name),
+            None => self.displayed(SIDEBAR),
+        };
+        let Some(objective) = objective else {
+            match name {
+                Some(name) => println!("There is no objective called {}", name),
+                None => println!("The sidebar is empty"),
+            }
+            return;
+        };
+
+        println!("{}", to_ansi(&objective.display_name));
+        let scores = objective.sorted_scores();
+        let shown = if name.is_none() { SIDEBAR_LINES } else { scores.len() };
+        for (entry, score) in scores.into_iter().take(shown) {
+            let entry = match all_players.team_of(entry) {
+                Some(team) => team.decorate(Component::text(entry)),
+                None => Component::text(entry),
+            };
+            let mut score = Component::text(&score.to_string());
+            score.style.color = Color::parse("red");
+            println!("{}  {}", to_ansi(&entry), to_ansi(&score));
+        }
+    }
+}
+
+pub fn display_scoreboard(buffer: Vec<u8>, scoreboard: Arc<Mutex<Scoreboard>>) -> io::Result<()> {
+    let mut reader = Cursor::new(buffer);
+    let slot = read_byte(&mut reader)?;
+    let objective = read_string(&mut reader)?;
+    scoreboard.lock().unwrap().set_display(slot, objective);
+    Ok(())
+}
+
+fn read_objective_info(reader: &mut Cursor<Vec<u8>>, objective: &mut Objective) -> io::Result<()> {
+    let json = read_string(reader)?;
+    objective.display_name =
+        serde_json::from_str(&json).map_err(|_| Error::new(ErrorKind::InvalidData, "Objective name is not a valid chat component"))?;
+    objective.score_type = match read_var_int_from_stream(reader)? {
+        0 => ScoreType::Integer,
+        1 => ScoreType::Hearts,
+        _ => return Err(Error::new(ErrorKind::InvalidData, "Unknown score type")),
+    };
+    Ok(())
+}
+
+pub fn scoreboard_objective(buffer: Vec<u8>, scoreboard: Arc<Mutex<Scoreboard>>) -> io::Result<()> {
+    let mut reader = Cursor::new(buffer);
+    let name = read_string(&mut reader)?;
+    let mode = read_byte(&mut reader)?;
+    match mode {
+        0 => {
+            let mut objective = Objective {
+                name,
+                ..Objective::default()
+            };
+            read_objective_info(&mut reader, &mut objective)?;
+            scoreboard.lock().unwrap().add_objective(objective);
+        }
+
+        1 => {
+            scoreboard.lock().unwrap().remove_objective(&name);
+        }
+
+        2 => {
+            let mut scoreboard = scoreboard.lock().unwrap();
+            if let Some(objective) = scoreboard.objective_mut(&name) {
+                read_objective_info(&mut reader, objective)?;
+            }
+        }
+
+        _ => {
+            return Err(Error::new(ErrorKind::InvalidData, "Unknown objective mode"));
+        }
+    }
+    Ok(())
+}
+
+pub fn update_score(buffer: Vec<u8>, scoreboard: Arc<Mutex<Scoreboard>>) -> io::Result<()> {
+    let mut reader = Cursor::new(buffer);
+    let entry = read_string(&mut reader)?;
+    let action = read_byte(&mut reader)?;
+    let objective = read_string(&mut reader)?;
+    match action {
+        0 => {
+            let value = read_var_int_from_stream(&mut reader)?;
+            scoreboard.lock().unwrap().set_score(&entry, &objective, value);
+        }
+
+        1 => {
+            scoreboard.lock().unwrap().remove_score(&entry, &objective);
+        }
+
+        _ => {
+            return Err(Error::new(ErrorKind::InvalidData, "Unknown score action"));
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::codec::{write_string, write_var_int};
+    use proptest::prelude::*;
+
+    fn objective_packet(name: &str, mode: u8, title: &str) -> Vec<u8> {
+        let mut buffer: Vec<u8> = Vec::new();
+        write_string(&mut buffer, name).unwrap();
+        buffer.push(mode);
+        if mode != 1 {
+            write_string(&mut buffer, title).unwrap();
+            write_var_int(&mut buffer, 0).unwrap();
+        }
+        buffer
+    }
+
+    fn score_packet(entry: &str, action: u8, objective: &str, value: i32) -> Vec<u8> {
+        let mut buffer: Vec<u8> = Vec::new();
+        write_string(&mut buffer, entry).unwrap();
+        buffer.push(action);
+        write_string(&mut buffer, objective).unwrap();
+        if action == 0 {
+            write_var_int(&mut buffer, value).unwrap();
+        }
+        buffer
+    }
+
+    fn display_packet(slot: u8, objective: &str) -> Vec<u8> {
+        let mut buffer: Vec<u8> = vec![slot];
+        write_string(&mut buffer, objective).unwrap();
+        buffer
+    }
+
+    #[test]
+    fn sidebar_follows_the_packets() {
+        let scoreboard = Arc::new(Mutex::new(Scoreboard::new()));
+        scoreboard_objective(objective_packet("kills", 0, r#"{"text":"Kills"}"#), Arc::clone(&scoreboard)).unwrap();
+        scoreboard_objective(objective_packet("deaths", 0, r#"{"text":"Deaths"}"#), Arc::clone(&scoreboard)).unwrap();
+        display_scoreboard(display_packet(SIDEBAR, "kills"), Arc::clone(&scoreboard)).unwrap();
+        for (entry, value) in [("Steve", 3), ("Alex", 5), ("Herobrine", 3), ("#hidden", 9)] {
+            update_score(score_packet(entry, 0, "kills", value), Arc::clone(&scoreboard)).unwrap();
+            update_score(score_packet(entry, 0, "deaths", value), Arc::clone(&scoreboard)).unwrap();
+        }
+        update_score(score_packet("Steve", 0, "missing", 1), Arc::clone(&scoreboard)).unwrap();
+        update_score(score_packet("Alex", 1, "", 0), Arc::clone(&scoreboard)).unwrap();
+        scoreboard_objective(objective_packet("kills", 2, r#"{"text":"Top kills"}"#), Arc::clone(&scoreboard)).unwrap();
+        {
+            let scoreboard = scoreboard.lock().unwrap();
+            let sidebar = scoreboard.displayed(SIDEBAR).unwrap();
+            assert_eq!(sidebar.display_name.to_plain_text(), "Top kills");
+            assert_eq!(sidebar.sorted_scores(), [("Herobrine", 3), ("Steve", 3)]);
+            assert!(!scoreboard.objective("deaths").unwrap().scores.contains_key("Alex"));
+            assert!(scoreboard.objective("missing").is_none());
+        }
+
+        scoreboard_objective(objective_packet("kills", 1, ""), Arc::clone(&scoreboard)).unwrap();
+        assert!(scoreboard.lock().unwrap().displayed(SIDEBAR).is_none());
+        display_scoreboard(display_packet(SIDEBAR, "deaths"), Arc::clone(&scoreboard)).unwrap();
+        display_scoreboard(display_packet(SIDEBAR, ""), Arc::clone(&scoreboard)).unwrap();
+        assert!(scoreboard.lock().unwrap().displayed(SIDEBAR).is_none());
+    }
+
+    #[test]
+    fn unknown_modes_are_errors() {
+        let scoreboard = Arc::new(Mutex::new(Scoreboard::new()));
+        assert!(scoreboard_objective(objective_packet("kills", 3, "{}"), Arc::clone(&scoreboard)).is_err());
+        assert!(update_score(score_packet("Steve", 2, "kills", 0), Arc::clone(&scoreboard)).is_err());
+        let mut buffer = objective_packet("kills", 0, "{}");
+        *buffer.last_mut().unwrap() = 2;
+        assert!(scoreboard_objective(buffer, scoreboard).is_err());
+    }
+
+    proptest! {
+        #[test]
+        fn scoreboard_packets_never_panic(bytes: Vec<u8>) {
+            let scoreboard = Arc::new(Mutex::new(Scoreboard::new()));
+            let _ = display_scoreboard(bytes.clone(), Arc::clone(&scoreboard));
+            let _ = scoreboard_objective(bytes.clone(), Arc::clone(&scoreboard));
+            let _ = update_score(bytes, scoreboard);
+        }
+    }
+}
@@ -0,0 +1,191 @@
+use std::io;
+use std::io::Cursor;
+use std::io::{Error, ErrorKind};
+use std::sync::{Arc, Mutex};
+use crate::codec::{read_byte, read_string, read_var_int_from_stream};
+use crate::component::{Color, Component, NAMED_COLORS};
+use crate::players::Players;
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Team {
+    pub name: String,
+    pub display_name: Component,
+    pub prefix: Component,
+    pub suffix: Component,
+    /// The color of member names, None for `reset` and the formatting codes.
+    pub color: Option<Color>,
+    pub name_tag_visibility: String,
+    pub collision_rule: String,
+    pub friendly_flags: u8,
+    /// Usernames of players, UUIDs of other entities.
+    pub members: Vec<String>,
+}
+
+impl Team {
+    /// A name as the tab list shows it: the prefix, the name in the team
+    /// color and the suffix.
+    pub fn decorate(&self, name: Component) -> Component {
+        let mut name = name;
+        if name.style.color.is_none() {
+            name.style.color = self.color;
+        }
+        Component {
+            extra: vec![self.prefix.clone(), name, self.suffix.clone()],
+            ..Component::default()
+        }
+    }
+}
+
+fn read_component(reader: &mut Cursor<Vec<u8>>) -> io::Result<Component> {
+    let json = read_string(reader)?;
+    serde_json::from_str(&json).map_err(|_| Error::new(ErrorKind::InvalidData, "Team text is not a valid chat component"))
+}
+
+fn read_team_info(reader: &mut Cursor<Vec<u8>>, team: &mut Team) -> io::Result<()> {
+    team.display_name = read_component(reader)?;
+    team.friendly_flags = read_byte(reader)?;
+    team.name_tag_visibility = read_string(reader)?;
+    team.collision_rule = read_string(reader)?;
+    // The first 16 formatting codes are the colors.
+    let color = read_var_int_from_stream(reader)?;
+    team.color = usize::try_from(color).ok().and_then(|color| NAMED_COLORS.get(color)).map(|(_, _, rgb)| Color(*rgb));
+    team.prefix = read_component(reader)?;
+    team.suffix = read_component(reader)?;
+    Ok(())
+}
+
+fn read_members(reader: &mut Cursor<Vec<u8>>) -> io::Result<Vec<String>> {
+    let count = read_var_int_from_stream(reader)?;
+    let mut members = Vec::<String>::new();
+    for _ in 0..count {
+        members.push(read_string(reader)?);
+    }
+    Ok(members)
+}
+
+pub fn teams(buffer: Vec<u8>, all_players: Arc<Mutex<Players>>) -> io::Result<()> {
+    let mut reader = Cursor::new(buffer);
+    let name = read_string(&mut reader)?;
+    let mode = read_byte(&mut reader)?;
+    match mode {
+        0 => {
+            let mut team = Team {
+                name,
+                ..Team::default()
+            };
+            read_team_info(&mut reader, &mut team)?;
+            team.members = read_members(&mut reader)?;
+            all_players.lock().unwrap().add_team(team);
+        }
+
+        1 => {
+            all_players.lock().unwrap().remove_team(&name);
+        }
+
+        2 => {
+            let mut all_players = all_players.lock().unwrap();
+            if let Some(team) = all_players.team_mut(&name) {
+                read_team_info(&mut reader, team)?;
+            }
+        }
+
+        3 => {
+            let members = read_members(&mut reader)?;
+            let mut all_players = all_players.lock().unwrap();
+            if let Some(team) = all_players.team_mut(&name) {
+                team.members.extend(members);
+            }
+        }
+
+        4 => {
+            let members = read_members(&mut reader)?;
+            let mut all_players = all_players.lock().unwrap();
+            if let Some(team) = all_players.team_mut(&name) {
+                team.members.retain(|member| !members.contains(member));
+            }
+        }
+
+        _ => {
+            return Err(Error::new(ErrorKind::InvalidData, "Unknown team mode"));
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::codec::{write_string, write_var_int};
+    use proptest::prelude::*;
+
+    fn write_team_info(buffer: &mut Vec<u8>, prefix: &str, color: i32) {
+        write_string(buffer, r#"{"text":"Red team"}"#).unwrap();
+        buffer.push(0x03);
+        write_string(buffer, "always").unwrap();
+        write_string(buffer, "always").unwrap();
+        write_var_int(buffer, color).unwrap();
+        write_string(buffer, prefix).unwrap();
+        write_string(buffer, r#"{"text":""}"#).unwrap();
+    }
+
+    fn team_packet(mode: u8, members: &[&str]) -> Vec<u8> {
+        let mut buffer: Vec<u8> = Vec::new();
+        write_string(&mut buffer, "red").unwrap();
+        buffer.push(mode);
+        if mode == 0 || mode == 2 {
+            write_team_info(&mut buffer, r#"{"text":"[Red] "}"#, 12);
+        }
+        if mode != 1 && mode != 2 {
+            write_var_int(&mut buffer, members.len() as i32).unwrap();
+            for member in members {
+                write_string(&mut buffer, member).unwrap();
+            }
+        }
+        buffer
+    }
+
+    #[test]
+    fn create_update_and_remove_teams() {
+        let all_players = Arc::new(Mutex::new(Players::new()));
+        teams(team_packet(0, &["Alex"]), Arc::clone(&all_players)).unwrap();
+        teams(team_packet(3, &["Steve", "Herobrine"]), Arc::clone(&all_players)).unwrap();
+        teams(team_packet(4, &["Herobrine"]), Arc::clone(&all_players)).unwrap();
+        {
+            let all_players = all_players.lock().unwrap();
+            let team = all_players.team_of("Steve").unwrap();
+            assert_eq!(team.members, ["Alex", "Steve"]);
+            assert_eq!(team.color, Color::parse("red"));
+            assert_eq!(team.friendly_flags, 3);
+            let name = team.decorate(Component::text("Steve"));
+            assert_eq!(name.to_plain_text(), "[Red] Steve");
+            assert_eq!(name.spans()[1].style.color, Color::parse("red"));
+        }
+
+        teams(team_packet(1, &[]), Arc::clone(&all_players)).unwrap();
+        assert!(all_players.lock().unwrap().team_of("Steve").is_none());
+    }
+
+    #[test]
+    fn reset_color_and_unknown_mode() {
+        let mut buffer: Vec<u8> = Vec::new();
+        write_string(&mut buffer, "plain").unwrap();
+        buffer.push(0x00);
+        write_team_info(&mut buffer, r#""""#, 21);
+        write_var_int(&mut buffer, 0).unwrap();
+        let all_players = Arc::new(Mutex::new(Players::new()));
+        teams(buffer, Arc::clone(&all_players)).unwrap();
+        assert_eq!(all_players.lock().unwrap().team("plain").unwrap().color, None);
+
+        let mut buffer: Vec<u8> = Vec::new();
+        write_string(&mut buffer, "plain").unwrap();
+        buffer.push(0x07);
+        assert!(teams(buffer, all_players).is_err());
+    }
+
+    proptest! {
+        #[test]
+        fn teams_never_panics(bytes: Vec<u8>) {
+            let _ = teams(bytes, Arc::new(Mutex::new(Players::new())));
+        }
+    }
+}
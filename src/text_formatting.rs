@@ -1,331 +1,281 @@
+use std::env;
 use std::io;
-use colored::{ColoredString, Colorize};
-use crate::read_json_from_file;
-use std::collections::HashMap;
-
-
-fn minecraft_to_ansi(s: String) -> String {
+use std::io::{Error, ErrorKind, IsTerminal};
+use std::sync::OnceLock;
+use crate::component::{ClickAction, Color, Component, Span, Style, NAMED_COLORS};
+use crate::interactions::{Interaction, Interactions};
+
+// Obfuscated text changes every frame in the game, a terminal gets it masked.
+fn mask(text: &str) -> String {
+    text.chars().map(|c| if c.is_whitespace() { c } else { '▒' }).collect()
+}
 
-    let mut color_codes = HashMap::new();
-    color_codes.insert('0', "\x1b[30m");
-    color_codes.insert('1', "\x1b[34m");
-    color_codes.insert('2', "\x1b[32m");
-    color_codes.insert('3', "\x1b[36m");
-    color_codes.insert('4', "\x1b[31m");
-    color_codes.insert('5', "\x1b[35m");
-    color_codes.insert('6', "\x1b[33m");
-    color_codes.insert('7', "\x1b[37m");
-    color_codes.insert('8', "\x1b[90m");
-    color_codes.insert('9', "\x1b[94m");
-    color_codes.insert('a', "\x1b[92m");
-    color_codes.insert('b', "\x1b[96m");
-    color_codes.insert('c', "\x1b[91m");
-    color_codes.insert('d', "\x1b[95m");
-    color_codes.insert('e', "\x1b[93m");
-    color_codes.insert('f', "\x1b[97m");
-    color_codes.insert('r', "\x1b[0m");
-    color_codes.insert('l', "\x1b[1m");
-    color_codes.insert('o', "\x1b[3m");
-    color_codes.insert('n', "\x1b[4m");
-    color_codes.insert('m', "\x1b[9m");
+/// How many colors the terminal can show.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum ColorSupport {
+    NoColor,
+    Ansi16,
+    Ansi256,
+    TrueColor,
+}
 
-    let mut result = String::new();
-    let mut chars = s.chars().peekable();
+impl ColorSupport {
+    /// Detects what stdout supports. Output that is not a terminal gets no
+    /// escape codes at all.
+    pub fn detect() -> ColorSupport {
+        ColorSupport::from_env(
+            env::var("NO_COLOR").ok().as_deref(),
+            env::var("COLORTERM").ok().as_deref(),
+            env::var("TERM").ok().as_deref(),
+            io::stdout().is_terminal(),
+        )
+    }
 
-    while let Some(ch) = chars.next() {
-        if ch == '§' {
-            if let Some(code) = chars.peek().and_then(|c| color_codes.get(c)) {
-                result.push_str(code);
-                let _ = chars.next();
-            }
-        } else {
-            result.push(ch);
+    fn from_env(no_color: Option<&str>, colorterm: Option<&str>, term: Option<&str>, is_terminal: bool) -> ColorSupport {
+        // https://no-color.org: any non-empty value turns colors off.
+        if !is_terminal || no_color.is_some_and(|value| !value.is_empty()) {
+            return ColorSupport::NoColor;
+        }
+        if matches!(colorterm, Some("truecolor" | "24bit")) {
+            return ColorSupport::TrueColor;
+        }
+        match term {
+            None | Some("dumb") => ColorSupport::NoColor,
+            Some(term) if term.ends_with("-direct") => ColorSupport::TrueColor,
+            Some(term) if term.contains("256color") => ColorSupport::Ansi256,
+            Some(_) => ColorSupport::Ansi16,
         }
     }
-
-    result
 }
-//
-
 
-fn hex_to_rgb(hex: &str) -> Result<[u8; 3], &str> {
-    if hex.len() != 6 {
-        return Err("Invalid hexadecimal color code");
-    }
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RenderOptions {
+    pub colors: ColorSupport,
+    /// Draws the vanilla text shadow as a dark background behind the text.
+    pub background_shading: bool,
+}
 
-    let r = u8::from_str_radix(&hex[0..2], 16).unwrap();
-    let g = u8::from_str_radix(&hex[2..4], 16).unwrap();
-    let b = u8::from_str_radix(&hex[4..6], 16).unwrap();
+static RENDER_OPTIONS: OnceLock<RenderOptions> = OnceLock::new();
 
-    Ok([r, g, b])
+/// Sets how chat is rendered for the rest of the program. Without a call,
+/// the detected color support is used with no background shading.
+pub fn set_render_options(options: RenderOptions) -> io::Result<()> {
+    RENDER_OPTIONS
+        .set(options)
+        .map_err(|_| Error::new(ErrorKind::AlreadyExists, "Render options are already set"))
 }
 
-pub fn mc_colors(color: &str) -> ([u8;3], [u8;3]) {
-    match color {
-        "black" => ([0, 0, 0], [0, 0, 0]),
-        "dark_blue" => ([0, 0, 170], [0, 0, 42]),
-        "dark_green" => ([0, 170, 0], [0, 42, 0]),
-        "dark_aqua" => ([0, 170, 170], [0, 42, 42]),
-        "dark_red" => ([170, 0, 0], [42, 0, 0]),
-        "dark_purple" => ([170, 0, 170], [42, 0, 42]),
-        "gold" => ([255, 170, 0], [63, 42, 0]),
-        "gray" => ([170, 170, 170], [42, 42, 42]),
-        "dark_gray" => ([85, 85, 85], [21, 21, 21]),
-        "blue" => ([85, 85, 255], [21, 21, 63]),
-        "green" => ([85, 255, 85], [21, 63, 21]),
-        "aqua" => ([85, 255, 255], [21, 63, 63]),
-        "red" => ([255, 85, 85], [63, 21, 21]),
-        "light_purple" => ([255, 85, 255], [63, 21, 63]),
-        "yellow" => ([255, 255, 85], [63, 63, 21]),
-        "white" => ([255, 255, 255], [63, 63, 63]),
-        _ => {
-            if color.starts_with('#') {
-                let mut rgb = [0; 3];
-                if let Some(stripped) = color.strip_prefix('#') {
-                    let hex = stripped;
-                    rgb = hex_to_rgb(hex).unwrap();
-                }
-                (rgb, [rgb[0]/4, rgb[1]/4, rgb[2]/4])
-            }
-            else {
-                ([255, 255, 255], [63, 63, 63])
-            
-            }
-        }
-    }
+fn render_options() -> RenderOptions {
+    *RENDER_OPTIONS.get_or_init(|| RenderOptions {
+        colors: ColorSupport::detect(),
+        background_shading: false,
+    })
 }
 
-#[derive(Clone)]
-pub struct Styles {
-    bold: bool,
-    italic: bool,
-    underlined: bool,
-    strikethrough: bool,
-    foreground: [u8; 3],
-    background: [u8; 3],
+// Vanilla draws a shadow a quarter as bright as the text color.
+fn shadow(color: Color) -> Color {
+    Color([color.0[0] / 4, color.0[1] / 4, color.0[2] / 4])
 }
 
-impl Styles {
-    pub fn new() -> Styles {
-        Styles {
-            bold: false,
-            italic: false,
-            underlined: false,
-            strikethrough: false,
-            foreground: [255, 255, 255],
-            background: [63, 63, 63],
-        }
-    }
-
-    pub fn copy_from(&mut self, other: &Styles) {
-        self.bold = other.bold;
-        self.italic = other.italic;
-        self.underlined = other.underlined;
-        self.strikethrough = other.strikethrough;
-        self.foreground = other.foreground;
-        self.background = other.background;
-    }
+fn distance(a: [u8; 3], b: [u8; 3]) -> u32 {
+    a.iter().zip(b).map(|(&a, b)| (a as i32 - b as i32).pow(2) as u32).sum()
+}
 
-    pub fn from_styles(inherited_format: &Styles) -> Styles {
-        let mut text_format = Styles::new();
-        text_format.copy_from(inherited_format);
-        text_format
+/// The nearest color of the xterm 256 color palette: its 6x6x6 cube or its
+/// gray ramp.
+fn to_ansi256(color: Color) -> u8 {
+    const CUBE_LEVELS: [u8; 6] = [0, 95, 135, 175, 215, 255];
+    let nearest_level = |value: u8| {
+        (0..6).min_by_key(|&i| (CUBE_LEVELS[i] as i32 - value as i32).abs()).unwrap_or(0)
+    };
+    let [r, g, b] = color.0.map(nearest_level);
+    let cube = [CUBE_LEVELS[r], CUBE_LEVELS[g], CUBE_LEVELS[b]];
+
+    let average = color.0.iter().map(|&c| c as u32).sum::<u32>() / 3;
+    let gray_index = (average.saturating_sub(8) / 10).min(23) as u8;
+    let gray_level = 8 + 10 * gray_index;
+
+    if distance(color.0, [gray_level; 3]) < distance(color.0, cube) {
+        232 + gray_index
+    } else {
+        16 + 36 * r as u8 + 6 * g as u8 + b as u8
     }
+}
 
-    pub fn from_obj(json_obj: &serde_json::Value, inherited_format: &Styles)  -> Styles {
-        let mut text_format = Styles::from_styles(inherited_format);
-        let content = json_obj.as_object().unwrap();
-
-        if content.contains_key("color") {
-            let color = content["color"].as_str().unwrap();
-            let (fg, bg) = mc_colors(color);
-            text_format.foreground = fg;
-            text_format.background = bg;
-        }
-
-        if content.contains_key("bold") && content["bold"].as_bool().unwrap(){
-            text_format.bold = true;
-        }
-
-        if content.contains_key("italic") && content["italic"].as_bool().unwrap() {
-            text_format.italic = true;
-        }
-
-        if content.contains_key("underlined") && content["underlined"].as_bool().unwrap(){
-            text_format.underlined = true;
-        }
-
-        if content.contains_key("strikethrough") && content["strikethrough"].as_bool().unwrap() {
-            text_format.strikethrough = true;
-        }
+/// The SGR foreground code of the nearest of the 16 named colors, which map
+/// one to one onto the 16 ANSI colors.
+fn to_ansi16(color: Color) -> u8 {
+    const CODES: [u8; 16] = [30, 34, 32, 36, 31, 35, 33, 37, 90, 94, 92, 96, 91, 95, 93, 97];
+    let nearest = NAMED_COLORS
+        .iter()
+        .enumerate()
+        .min_by_key(|(_, (_, _, rgb))| distance(color.0, *rgb))
+        .map_or(0, |(i, _)| i);
+    CODES[nearest]
+}
 
-        text_format
+/// The SGR parameters of a foreground (`layer` 38) or background (`layer` 48)
+/// color.
+fn color_code(color: Color, layer: u8, colors: ColorSupport) -> Option<String> {
+    let [r, g, b] = color.0;
+    match colors {
+        ColorSupport::NoColor => None,
+        ColorSupport::Ansi16 => Some((to_ansi16(color) + layer - 38).to_string()),
+        ColorSupport::Ansi256 => Some(format!("{};5;{}", layer, to_ansi256(color))),
+        ColorSupport::TrueColor => Some(format!("{};2;{};{};{}", layer, r, g, b)),
     }
 }
 
-pub fn print_string(text: String, text_format: &Styles) {
-    let string = minecraft_to_ansi(text);
-    let mut colorised: ColoredString = string.normal();
+// Server text must not reach the terminal as escape sequences of its own,
+// such as a fake link or a clipboard write. Line breaks and tabs are kept.
+fn strip_control(text: &str) -> String {
+    text.chars().filter(|&c| !c.is_control() || c == '\n' || c == '\t').collect()
+}
 
-    if text_format.bold {
-        colorised = colorised.bold();
+fn format_string(text: String, text_format: &Style, options: RenderOptions) -> String {
+    let text = strip_control(&text);
+    let string = if text_format.obfuscated == Some(true) { mask(&text) } else { text };
+    if options.colors == ColorSupport::NoColor {
+        return string;
     }
 
-    if text_format.italic {
-        colorised = colorised.italic();
+    let mut codes = Vec::<String>::new();
+    let attributes = [
+        (text_format.bold, "1"),
+        (text_format.italic, "3"),
+        (text_format.underlined, "4"),
+        (text_format.strikethrough, "9"),
+    ];
+    for (enabled, code) in attributes {
+        if enabled == Some(true) {
+            codes.push(code.to_string());
+        }
     }
 
-    if text_format.underlined {
-        colorised = colorised.underline();
+    // Unstyled text keeps the terminal's own color, unless it is drawn on the
+    // dark shading where only the vanilla white is readable.
+    let foreground = match text_format.color {
+        Some(color) => Some(color),
+        None if options.background_shading => Some(Color::WHITE),
+        None => None,
+    };
+    if let Some(foreground) = foreground {
+        codes.extend(color_code(foreground, 38, options.colors));
+        if options.background_shading {
+            codes.extend(color_code(shadow(foreground), 48, options.colors));
+        }
     }
 
-    if text_format.strikethrough {
-        colorised = colorised.strikethrough();
+    if codes.is_empty() {
+        string
+    } else {
+        format!("\x1b[{}m{}\x1b[0m", codes.join(";"), string)
     }
-
-    colorised = colorised.truecolor(text_format.foreground[0], text_format.foreground[1], text_format.foreground[2]).on_truecolor(text_format.background[0], text_format.background[1], text_format.background[2]);
-
-    print!("{}", colorised);
 }
 
-pub fn parse_json_obj(json_obj: serde_json::Value, inherited_format: Styles) -> io::Result<()> {
-    let content = json_obj.as_object().unwrap();
-    let text_format = Styles::from_obj(&json_obj, &inherited_format);
-    if content.contains_key("text") && content["text"].is_string() {
-        print_string(content["text"].to_string(), &text_format.clone());
-    }
-
-    if content.contains_key("translate") && content["translate"].is_string() {
-        let translate_msg = translate(content["translate"].as_str().unwrap(), content["with"].clone(), text_format.clone())?;
-        print_string(translate_msg, &text_format.clone());
-    }
-
-    if content.contains_key("extra") {
-        if content["extra"].is_object(){
-             parse_json_obj(content["extra"].clone(), text_format.clone())?;
-        }
-        if content["extra"].is_array() {
-            parse_json_array(content["extra"].clone(), text_format.clone())?;
-        }
-    }
-    Ok(())
+// The vanilla client only opens web links.
+fn hyperlink_target(style: &Style) -> Option<&str> {
+    let click_event = style.click_event.as_ref()?;
+    let url = click_event.value.as_str();
+    let is_web_link = url.starts_with("http://") || url.starts_with("https://");
+    // A control character would end the escape sequence early.
+    (click_event.action == ClickAction::OpenUrl && is_web_link && !url.chars().any(char::is_control)).then_some(url)
 }
 
-pub fn parse_json_array(json_array: serde_json::Value, inherited_format: Styles) -> io::Result<()> {
-
-    let content = json_array.as_array().unwrap();
-    let text_format: Styles = Styles::from_styles(&inherited_format);
-    for item in content {
-        if item.is_object() {
-            parse_json_obj(item.clone(), text_format.clone())?;
-        }
-        if item.is_array() {
-            parse_json_array(item.clone(), text_format.clone())?;
-        }
-        if item.is_string() {
-            let msg = item.as_str().unwrap().to_string();
-            print_string(msg, &text_format.clone());
-        }
+/// Renders a span, as an OSC 8 hyperlink if it opens a web link.
+fn format_span(span: &Span) -> String {
+    let options = render_options();
+    let text = format_string(span.text.clone(), &span.style, options);
+    match hyperlink_target(&span.style) {
+        Some(url) if options.colors != ColorSupport::NoColor => format!("\x1b]8;;{}\x1b\\{}\x1b]8;;\x1b\\", url, text),
+        _ => text,
     }
-
-    Ok(())
 }
 
-fn replace_placeholders(message: &str, replacements: &mut [String]) -> String {
-    let mut replaced_message = message.to_string();
-    let mut index = 0;
+/// Renders a component with ANSI escape codes.
+pub fn to_ansi(component: &Component) -> String {
+    component.spans().iter().map(format_span).collect()
+}
 
-    while let Some(pos) = replaced_message.find("%s") {
-        if let Some(replacement) = replacements.get(index) {
-            replaced_message.replace_range(pos..pos+2, replacement);
-            index += 1;
-        } else {
-            break;
+/// Renders a component like `to_ansi`, following every clickable or hoverable
+/// part with the number `/click` and `/hover` know it by.
+pub fn to_ansi_numbered(component: &Component, interactions: &mut Interactions) -> String {
+    let spans = component.spans();
+    let mut result = String::new();
+    let mut start = 0;
+    while start < spans.len() {
+        // Neighbouring spans with the same events are one part, like a link
+        // made of several colors.
+        let interaction = Interaction::of(&spans[start].style);
+        let end = spans[start..]
+            .iter()
+            .position(|span| Interaction::of(&span.style) != interaction)
+            .map_or(spans.len(), |length| start + length);
+        for span in &spans[start..end] {
+            result.push_str(&format_span(span));
         }
+        if let Some(interaction) = interaction {
+            let id = interactions.register(interaction);
+            let marker = format!("[{}]", id);
+            if render_options().colors == ColorSupport::NoColor {
+                result.push_str(&marker);
+            } else {
+                result.push_str(&format!("\x1b[2m{}\x1b[0m", marker));
+            }
+        }
+        start = end;
     }
-
-    for (i, replacement) in replacements.iter().enumerate() {
-        let placeholder = format!("%{}$s", i + 1);
-        replaced_message = replaced_message.replace(&placeholder, replacement);
-    }
-
-    replaced_message = replaced_message.replace("%%", "%");
-
-    replaced_message
+    result
 }
 
-pub fn translate (translation: &str, with: serde_json::Value, inherited_format: Styles) -> io::Result<String> {
-    let translate_file = read_json_from_file("src/translations")?;
-    let content = translate_file.as_object().unwrap();
-    if !content.contains_key(translation) {
-        return Err(io::Error::new(io::ErrorKind::InvalidData, "Translation not found."));
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn color_support_from_environment() {
+        assert_eq!(ColorSupport::from_env(None, Some("truecolor"), Some("xterm"), false), ColorSupport::NoColor);
+        assert_eq!(ColorSupport::from_env(Some("1"), Some("truecolor"), Some("xterm"), true), ColorSupport::NoColor);
+        assert_eq!(ColorSupport::from_env(Some(""), Some("24bit"), None, true), ColorSupport::TrueColor);
+        assert_eq!(ColorSupport::from_env(None, None, Some("xterm-256color"), true), ColorSupport::Ansi256);
+        assert_eq!(ColorSupport::from_env(None, None, Some("xterm-direct"), true), ColorSupport::TrueColor);
+        assert_eq!(ColorSupport::from_env(None, None, Some("linux"), true), ColorSupport::Ansi16);
+        assert_eq!(ColorSupport::from_env(None, None, Some("dumb"), true), ColorSupport::NoColor);
     }
 
-    let mut translated_message = String::new();
-
-    if content[translation].is_string() {
-       translated_message = content[translation].as_str().unwrap().to_string();
+    #[test]
+    fn colors_are_downsampled() {
+        assert_eq!(to_ansi256(Color([0, 0, 0])), 16);
+        assert_eq!(to_ansi256(Color([255, 85, 85])), 203);
+        assert_eq!(to_ansi256(Color([128, 128, 128])), 244);
+        assert_eq!(to_ansi16(Color::parse("gold").unwrap()), 33);
+        assert_eq!(to_ansi16(Color([250, 90, 80])), 91);
+        assert_eq!(color_code(Color::parse("dark_gray").unwrap(), 48, ColorSupport::Ansi16).as_deref(), Some("100"));
     }
 
-    let mut with_vec = Vec::<String>::new();
-
-    if with.is_object() {
-        with_vec = parse_with_obj(with, inherited_format)?;
-    }
-    else if with.is_array() {
-        with_vec = parse_with_array(with, inherited_format.clone())?;
-    }
-    else if with.is_string() {
-        let string = with.as_str().unwrap().to_string();
-        with_vec.push(string);
+    #[test]
+    fn background_shading_is_optional() {
+        let style = Style {
+            color: Color::parse("red"),
+            bold: Some(true),
+            ..Style::default()
+        };
+        let options = |colors, background_shading| RenderOptions { colors, background_shading };
+        assert_eq!(format_string("a".to_string(), &style, options(ColorSupport::TrueColor, false)), "\x1b[1;38;2;255;85;85ma\x1b[0m");
+        assert_eq!(format_string("a".to_string(), &style, options(ColorSupport::Ansi256, true)), "\x1b[1;38;5;203;48;5;234ma\x1b[0m");
+        assert_eq!(format_string("a".to_string(), &style, options(ColorSupport::NoColor, true)), "a");
+        assert_eq!(format_string("a".to_string(), &Style::default(), options(ColorSupport::Ansi16, false)), "a");
+        let obfuscated = Style {
+            obfuscated: Some(true),
+            ..Style::default()
+        };
+        assert_eq!(format_string("ab c".to_string(), &obfuscated, options(ColorSupport::NoColor, false)), "▒▒ ▒");
     }
 
-    let message = replace_placeholders(&translated_message, &mut with_vec);
-
-    Ok(message)
-}
-
-pub fn parse_with_obj(with: serde_json::Value, inherited_format: Styles) -> io::Result<Vec<String>> {
-    let content = with.as_object().unwrap();
-    let mut text_vec = Vec::<String>::new();
-    if content.contains_key("text") && content["text"].is_string() {
-        let string = content["text"].as_str().unwrap().to_string();
-        text_vec.push(string);
+    #[test]
+    fn server_text_cannot_inject_escape_sequences() {
+        let options = RenderOptions { colors: ColorSupport::TrueColor, background_shading: false };
+        let text = "a\x1b]52;c;ZWNobw==\x07b\u{9b}2Jc\x1b]8;;https://example.com\x1b\\d\n\te";
+        assert_eq!(format_string(text.to_string(), &Style::default(), options), "a]52;c;ZWNobw==b2Jc]8;;https://example.com\\d\n\te");
     }
-
-    if content.contains_key("extra") {
-        if content["extra"].is_object(){
-            let mut vec = parse_with_obj(content["extra"].clone(), inherited_format.clone())?;
-            text_vec.append(&mut vec);
-        }
-        if content["extra"].is_array() {
-            let mut vec = parse_with_array(content["extra"].clone(), inherited_format.clone())?;
-            text_vec.append(&mut vec);
-        }
-    }
- 
-    Ok(text_vec)
 }
-
-pub fn parse_with_array(with: serde_json::Value, inherited_format: Styles) -> io::Result<Vec<String>> {
-    let content = with.as_array().unwrap();
-    let mut text_vec = Vec::<String>::new();
-    let text_format: Styles = Styles::from_styles(&inherited_format);
-
-    for item in content {
-        if item.is_object() {
-            let msg = parse_with_obj(item.clone(), text_format.clone());
-            text_vec.append(&mut msg?);
-        }
-        if item.is_array() {
-            let msg = parse_with_array(item.clone(), text_format.clone());
-            text_vec.append(&mut msg?);
-        }
-        if item.is_string() {
-            let msg = item.as_str().unwrap().to_string();
-            text_vec.push(msg);
-        }
-    }
-
-    Ok(text_vec)
-}
\ No newline at end of file
@@ -0,0 +1,155 @@
+use std::io;
+use std::io::Cursor;
+use std::io::{Error, ErrorKind};
+use crate::codec::{read_bool, read_int, read_string};
+use crate::component::{Color, Component};
+
+/// Ticks a title fades in, stays and fades out for until the server sets
+/// other times.
+pub const DEFAULT_TIMES: TitleTimes = TitleTimes {
+    fade_in: 10,
+    stay: 70,
+    fade_out: 20,
+};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TitleTimes {
+    pub fade_in: i32,
+    pub stay: i32,
+    pub fade_out: i32,
+}
+
+impl TitleTimes {
+    /// How long a title is on screen, in seconds.
+    pub fn seconds(&self) -> f32 {
+        (self.fade_in.max(0) + self.stay.max(0) + self.fade_out.max(0)) as f32 / 20.0
+    }
+}
+
+/// The title shown in the middle of the screen. Like in the game a subtitle
+/// waits for the next title.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Titles {
+    pub subtitle: Option<Component>,
+    pub times: TitleTimes,
+}
+
+impl Default for Titles {
+    fn default() -> Titles {
+        Titles {
+            subtitle: None,
+            times: DEFAULT_TIMES,
+        }
+    }
+}
+
+impl Titles {
+    pub fn new() -> Titles {
+        Titles::default()
+    }
+
+    /// A title as a chat line: the title in bold, the subtitle and how long
+    /// the game shows them.
+    pub fn title_line(&self, title: &Component) -> Component {
+        let mut title = title.clone();
+        if title.style.bold.is_none() {
+            title.style.bold = Some(true);
+        }
+        let mut extra = vec![Component::text("[Title] "), title];
+        if let Some(subtitle) = &self.subtitle {
+            extra.push(Component::text(" - "));
+            extra.push(subtitle.clone());
+        }
+        let mut seconds = Component::text(&format!(" ({}s)", self.times.seconds()));
+        seconds.style.color = Color::parse("gray");
+        extra.push(seconds);
+        Component {
+            extra,
+            ..Component::default()
+        }
+    }
+
+    pub fn clear(&mut self, reset: bool) {
+        self.subtitle = None;
+        if reset {
+            self.times = DEFAULT_TIMES;
+        }
+    }
+}
+
+fn read_text(buffer: Vec<u8>) -> io::Result<Component> {
+    let json = read_string(&mut Cursor::new(buffer))?;
+    serde_json::from_str(&json).map_err(|_| Error::new(ErrorKind::InvalidData, "Title is not a valid chat component"))
+}
+
+/// Reads a Set Title Text packet and returns the line to show for it.
+pub fn title(buffer: Vec<u8>, titles: &mut Titles) -> io::Result<Component> {
+    let title = read_text(buffer)?;
+    Ok(titles.title_line(&title))
+}
+
+pub fn subtitle(buffer: Vec<u8>, titles: &mut Titles) -> io::Result<()> {
+    titles.subtitle = Some(read_text(buffer)?);
+    Ok(())
+}
+
+pub fn title_times(buffer: Vec<u8>, titles: &mut Titles) -> io::Result<()> {
+    let mut reader = Cursor::new(buffer);
+    titles.times = TitleTimes {
+        fade_in: read_int(&mut reader)?,
+        stay: read_int(&mut reader)?,
+        fade_out: read_int(&mut reader)?,
+    };
+    Ok(())
+}
+
+pub fn clear_titles(buffer: Vec<u8>, titles: &mut Titles) -> io::Result<()> {
+    let reset = read_bool(&mut Cursor::new(buffer))?;
+    titles.clear(reset);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::codec::{write_bool, write_int, write_string};
+
+    fn text_packet(json: &str) -> Vec<u8> {
+        let mut buffer: Vec<u8> = Vec::new();
+        write_string(&mut buffer, json).unwrap();
+        buffer
+    }
+
+    #[test]
+    fn titles_carry_subtitle_and_times() {
+        let mut titles = Titles::new();
+        let line = title(text_packet(r#"{"text":"Welcome"}"#), &mut titles).unwrap();
+        assert_eq!(line.to_plain_text(), "[Title] Welcome (5s)");
+        assert_eq!(line.spans()[1].style.bold, Some(true));
+
+        let mut times: Vec<u8> = Vec::new();
+        for ticks in [0, 40, 10] {
+            write_int(&mut times, ticks).unwrap();
+        }
+        title_times(times, &mut titles).unwrap();
+        subtitle(text_packet(r#""to the event""#), &mut titles).unwrap();
+        let line = title(text_packet(r#"{"text":"Round 1","bold":false}"#), &mut titles).unwrap();
+        assert_eq!(line.to_plain_text(), "[Title] Round 1 - to the event (2.5s)");
+        assert_eq!(line.spans()[1].style.bold, Some(false));
+
+        let mut clear: Vec<u8> = Vec::new();
+        write_bool(&mut clear, false).unwrap();
+        clear_titles(clear.clone(), &mut titles).unwrap();
+        assert_eq!(titles.subtitle, None);
+        assert_eq!(titles.times.stay, 40);
+        clear[0] = 1;
+        clear_titles(clear, &mut titles).unwrap();
+        assert_eq!(titles, Titles::new());
+    }
+
+    #[test]
+    fn invalid_title_is_an_error() {
+        assert!(title(text_packet("{"), &mut Titles::new()).is_err());
+        assert!(title_times(vec![0, 0, 0], &mut Titles::new()).is_err());
+    }
+}
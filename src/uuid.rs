@@ -0,0 +1,82 @@
+use std::fmt;
+use std::str::FromStr;
+use md5::{Digest, Md5};
+
+/// A 128-bit UUID as sent by the server, most significant bits first.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Uuid(pub u128);
+
+impl Uuid {
+    pub fn as_bytes(&self) -> [u8; 16] {
+        self.0.to_be_bytes()
+    }
+
+    /// The UUID an offline-mode server gives a player: a version 3 UUID of
+    /// `OfflinePlayer:<name>`, the same as Java's `UUID.nameUUIDFromBytes`.
+    pub fn offline_player(username: &str) -> Uuid {
+        let mut bytes: [u8; 16] = Md5::digest(format!("OfflinePlayer:{}", username)).into();
+        bytes[6] = (bytes[6] & 0x0f) | 0x30;
+        bytes[8] = (bytes[8] & 0x3f) | 0x80;
+        Uuid(u128::from_be_bytes(bytes))
+    }
+
+    pub fn version(&self) -> u8 {
+        ((self.0 >> 76) & 0xf) as u8
+    }
+}
+
+impl fmt::Display for Uuid {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let hex = format!("{:032x}", self.0);
+        write!(f, "{}-{}-{}-{}-{}", &hex[0..8], &hex[8..12], &hex[12..16], &hex[16..20], &hex[20..32])
+    }
+}
+
+/// Parses both the hyphenated form and the 32 digit form Mojang's API uses.
+impl FromStr for Uuid {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<Uuid, Self::Err> {
+        let hex: String = if s.len() == 36 {
+            let hyphens_in_place = s.char_indices()
+                .filter(|(_, c)| *c == '-')
+                .map(|(i, _)| i)
+                .eq([8, 13, 18, 23]);
+            if !hyphens_in_place {
+                return Err("Invalid UUID");
+            }
+            s.replace('-', "")
+        } else {
+            s.to_string()
+        };
+
+        if hex.len() != 32 || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+            return Err("Invalid UUID");
+        }
+        u128::from_str_radix(&hex, 16).map(Uuid).map_err(|_| "Invalid UUID")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn display_and_parse() {
+        let uuid = Uuid(0x069a79f4_44e9_4726_a5be_fca90e38aaf5);
+        assert_eq!(uuid.to_string(), "069a79f4-44e9-4726-a5be-fca90e38aaf5");
+        assert_eq!("069a79f4-44e9-4726-a5be-fca90e38aaf5".parse::<Uuid>(), Ok(uuid));
+        assert_eq!("069A79F444E94726A5BEFCA90E38AAF5".parse::<Uuid>(), Ok(uuid));
+        assert!("069a79f4-44e9-4726-a5be-fca90e38aaf".parse::<Uuid>().is_err());
+        assert!("069a79f444e9-4726-a5be-fca90e38-aaf5".parse::<Uuid>().is_err());
+        assert!("+69a79f444e94726a5befca90e38aaf5".parse::<Uuid>().is_err());
+    }
+
+    #[test]
+    fn offline_player_uuid() {
+        // The well known offline-mode UUID of Notch.
+        let uuid = Uuid::offline_player("Notch");
+        assert_eq!(uuid.to_string(), "b50ad385-829d-3141-a216-7e7d7539ba7f");
+        assert_eq!(uuid.version(), 3);
+    }
+}